@@ -1,10 +1,82 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, Burn, Mint, MintTo, SetAuthority, Token, TokenAccount, Transfer};
 
 declare_id!("212XVhDqD21uFt1DfCuJ7WkVjcZZQCZRHDi3qeXTCqCH");
 
-// Pyth Network 价格预言机账户（需要根据实际部署调整）
-const PYTH_PROGRAM_ID: &str = "FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH";
+// Pyth Network 价格预言机程序（需要根据实际部署调整）
+const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+
+// Pyth v2 价格账户格式
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_VERSION_2: u32 = 2;
+const PYTH_ACCOUNT_TYPE_PRICE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_PRICE_ACCOUNT_MIN_LEN: usize = 240;
+
+// pump.fun bonding curve 程序及账户格式
+const PUMP_FUN_PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+const RUG_PULL_CONFIRMATION_SLOTS: u64 = 150; // 储备跌幅需持续的 slot 数 (约 1 分钟)
+const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
+const BONDING_CURVE_MIN_LEN: usize = 49;
+
+// 预言机价格校验参数上限
+const MAX_ORACLE_STALENESS: i64 = 3600;   // 最大价格延迟 (秒)
+const PRICE_DECIMALS: i32 = 18;           // 返回价格统一为 18 位小数 (u128)，保留低价代币的精度
+const MAX_SCALED_PRICE: u128 = u128::MAX / 10000; // 换算后价格上限，保证跌幅计算不溢出
+
+// 国库费率上限 (基点)
+const MAX_TREASURY_FEE: u16 = 2000;
+
+// 产品注册表最多容纳的在售产品数
+const MAX_REGISTRY_PRODUCTS: usize = 32;
+
+// 乐观理赔挑战期上限 (秒)
+const MAX_CHALLENGE_PERIOD: i64 = 30 * 86400;
+
+// 续保无理赔优惠上限 (基点)
+const MAX_NO_CLAIMS_DISCOUNT: u16 = 5000;
+
+// 保单到期后理赔宽限期上限 (秒)
+const MAX_CLAIM_GRACE_PERIOD: i64 = 30 * 86400;
+
+// 到期前可续保的时间窗口 (秒)
+const RENEWAL_WINDOW: i64 = 7 * 86400;
+
+// 理赔申诉: 拒绝后的申诉期限 (秒) 及最多申诉次数
+const APPEAL_WINDOW: i64 = 7 * 86400;
+const MAX_APPEALS: u8 = 2;
+
+// 管理操作时间锁: 默认延迟及允许的延迟范围 (秒)
+const DEFAULT_TIMELOCK_DELAY: i64 = 48 * 3600;
+const MIN_TIMELOCK_DELAY: i64 = 3600;
+const MAX_TIMELOCK_DELAY: i64 = 30 * 86400;
+
+// 暂停标志位，协议与产品共用
+pub const PAUSE_PURCHASES: u8 = 1 << 0;     // 投保、续保
+pub const PAUSE_CLAIMS: u8 = 1 << 1;        // 提交理赔、申诉
+pub const PAUSE_PAYOUTS: u8 = 1 << 2;       // 理赔赔付
+pub const PAUSE_CANCELLATIONS: u8 = 1 << 3; // 退保
+pub const PAUSE_CAPITAL: u8 = 1 << 4;       // 承保资金存取 (仅协议级)
+pub const PAUSE_ALL: u8 =
+    PAUSE_PURCHASES | PAUSE_CLAIMS | PAUSE_PAYOUTS | PAUSE_CANCELLATIONS | PAUSE_CAPITAL;
+
+// 理赔评估委员会最大成员数
+const MAX_COMMITTEE_MEMBERS: usize = 10;
+
+// 承保杠杆 (基点, 10000 = 在保保额不超过资金池余额)
+const DEFAULT_MAX_LEVERAGE: u16 = 10000;
+const MAX_LEVERAGE_CAP: u16 = 50000;
+
+// 冷却期结束后须在此期限内执行赎回，逾期申请失效
+const WITHDRAWAL_EXECUTION_WINDOW: i64 = 3 * 86400;
+
+// 首次注资时永久锁定在托管账户的份额，抬高通过直接转账操纵份额价格的成本
+const MIN_LOCKED_SHARES: u64 = 1_000;
+
+// 每份额净值低于初始 1:1 价格的 1 / DRAINED_POOL_RATIO 时视为资金池耗尽
+const DRAINED_POOL_RATIO: u128 = 1_000;
 
 #[program]
 pub mod cowguard_insurance {
@@ -15,204 +87,346 @@ pub mod cowguard_insurance {
         ctx: Context<InitializeProtocol>,
         treasury_fee: u16,  // 国库费率 (基点)
     ) -> Result<()> {
+        require!(treasury_fee <= MAX_TREASURY_FEE, ErrorCode::InvalidTreasuryFee);
+
         let protocol = &mut ctx.accounts.protocol;
         protocol.authority = ctx.accounts.authority.key();
+        protocol.pending_authority = Pubkey::default();
+        protocol.guardian = ctx.accounts.authority.key();
+        protocol.claims_assessor = ctx.accounts.authority.key();
+        protocol.assessor_committee = Pubkey::default();
         protocol.treasury = ctx.accounts.treasury.key();
         protocol.treasury_fee = treasury_fee;
         protocol.total_policies = 0;
+        protocol.product_count = 0;
         protocol.total_claims = 0;
         protocol.total_payouts = 0;
-        protocol.is_paused = false;
+        protocol.total_premium_fees = 0;
+        protocol.total_cancellation_fees = 0;
+        protocol.pause_flags = 0;
+        protocol.active_coverage = 0;
+        protocol.max_leverage = DEFAULT_MAX_LEVERAGE;
+        protocol.product_timelock = DEFAULT_TIMELOCK_DELAY;
+        protocol.oracle_timelock = DEFAULT_TIMELOCK_DELAY;
+        protocol.fee_timelock = DEFAULT_TIMELOCK_DELAY;
+        protocol.queued_action_count = 0;
         protocol.bump = ctx.bumps.protocol;
 
+        let registry = &mut ctx.accounts.product_registry;
+        registry.products = Vec::new();
+        registry.bump = ctx.bumps.product_registry;
+
+        emit!(ProtocolInitialized {
+            protocol: protocol.key(),
+            authority: protocol.authority,
+            treasury: protocol.treasury,
+            treasury_fee,
+            max_leverage: protocol.max_leverage,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         msg!("CowGuard Insurance Protocol initialized");
         Ok(())
     }
 
-    /// 创建保险产品
-    pub fn create_product(
-        ctx: Context<CreateProduct>,
-        product_type: InsuranceType,
-        premium_rate: u16,      // 保费率 (基点)
-        coverage_rate: u16,     // 赔付率 (基点, 10000 = 100%)
-        min_coverage: u64,      // 最小保额
-        max_coverage: u64,      // 最大保额
-        duration_days: u16,     // 保险期限 (天)
+    /// 初始化承保资金池 (仅限管理员)
+    /// 创建协议 PDA 持有的资金池、承保份额代币及赎回托管账户
+    pub fn initialize_capital_pool(
+        ctx: Context<InitializeCapitalPool>,
+        withdrawal_cooldown: i64,   // 赎回冷却期 (秒)
     ) -> Result<()> {
-        require!(premium_rate > 0 && premium_rate <= 2000, ErrorCode::InvalidPremiumRate);
-        require!(coverage_rate > 0 && coverage_rate <= 10000, ErrorCode::InvalidCoverageRate);
-        require!(min_coverage < max_coverage, ErrorCode::InvalidCoverageRange);
+        require!(withdrawal_cooldown >= 0, ErrorCode::InvalidWithdrawalCooldown);
 
-        let product = &mut ctx.accounts.product;
-        product.authority = ctx.accounts.authority.key();
-        product.product_type = product_type;
-        product.premium_rate = premium_rate;
-        product.coverage_rate = coverage_rate;
-        product.min_coverage = min_coverage;
-        product.max_coverage = max_coverage;
-        product.duration_days = duration_days;
-        product.total_policies = 0;
-        product.total_coverage = 0;
-        product.is_active = true;
-        product.bump = ctx.bumps.product;
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.capital_mint = ctx.accounts.capital_mint.key();
+        protocol.insurance_pool = ctx.accounts.insurance_pool.key();
+        protocol.share_mint = ctx.accounts.share_mint.key();
+        protocol.share_escrow = ctx.accounts.share_escrow.key();
+        protocol.withdrawal_cooldown = withdrawal_cooldown;
+        protocol.pending_withdrawal_shares = 0;
+        protocol.share_epoch = 0;
 
-        msg!("Insurance product created: {:?}", product_type);
+        emit!(CapitalPoolInitialized {
+            capital_mint: protocol.capital_mint,
+            insurance_pool: protocol.insurance_pool,
+            share_mint: protocol.share_mint,
+            withdrawal_cooldown,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Capital pool initialized: mint={}, cooldown={}",
+            protocol.capital_mint,
+            withdrawal_cooldown
+        );
         Ok(())
     }
 
-    /// 购买保险
-    pub fn purchase_insurance(
-        ctx: Context<PurchaseInsurance>,
-        coverage_amount: u64,
-    ) -> Result<()> {
-        let protocol = &ctx.accounts.protocol;
-        let product = &mut ctx.accounts.product;
-        
-        require!(!protocol.is_paused, ErrorCode::ProtocolPaused);
-        require!(product.is_active, ErrorCode::ProductInactive);
+    /// 资金池耗尽后重新募资 (仅限管理员)
+    /// 耗尽的资金池中旧份额几乎没有净值，按其定价的注资会铸造超出 u64 的份额。改用新一代份额代币与托管账户，
+    /// 旧份额 (含待赎回份额) 作废，资金池残余资金在新一代首次注资时锁定，此后重新按 1:1 注资
+    pub fn recapitalize_capital_pool(ctx: Context<RecapitalizeCapitalPool>, share_epoch: u32) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        require!(share_epoch == protocol.share_epoch + 1, ErrorCode::InvalidShareEpoch);
+
+        let residual_balance = ctx.accounts.insurance_pool.amount;
+        let written_off_shares = ctx.accounts.share_mint.supply;
         require!(
-            coverage_amount >= product.min_coverage && coverage_amount <= product.max_coverage,
-            ErrorCode::InvalidCoverageAmount
+            pool_drained(residual_balance, written_off_shares),
+            ErrorCode::PoolNotDrained
         );
 
-        // 保存用于计算的值
-        let premium_rate = product.premium_rate;
-        let duration_days = product.duration_days;
-        let product_key = product.key();
+        protocol.share_mint = ctx.accounts.new_share_mint.key();
+        protocol.share_escrow = ctx.accounts.new_share_escrow.key();
+        protocol.share_epoch = share_epoch;
+        protocol.pending_withdrawal_shares = 0;
 
-        // 计算保费
-        let premium = coverage_amount
-            .checked_mul(premium_rate as u64)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
+        emit!(CapitalPoolRecapitalized {
+            share_epoch,
+            share_mint: protocol.share_mint,
+            written_off_shares,
+            residual_balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Capital pool recapitalized: epoch={}, written_off_shares={}, residual={}",
+            share_epoch,
+            written_off_shares,
+            residual_balance
+        );
+        Ok(())
+    }
+
+    /// 开设承保人账户
+    pub fn open_underwriter_position(ctx: Context<OpenUnderwriterPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.underwriter.key();
+        position.pending_shares = 0;
+        position.withdrawal_requested_at = 0;
+        position.share_epoch = 0;
+        position.bump = ctx.bumps.position;
+
+        msg!("Underwriter position opened for {}", position.owner);
+        Ok(())
+    }
+
+    /// 注入承保资本，按资金池净值铸造份额代币
+    pub fn deposit_capital(ctx: Context<DepositCapital>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.protocol.is_paused(PAUSE_CAPITAL), ErrorCode::ProtocolPaused);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let (shares, locked_shares) = deposit_shares(
+            amount,
+            ctx.accounts.insurance_pool.amount,
+            ctx.accounts.share_mint.supply,
+        )?;
 
-        // 转移保费到保险池
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.user_token_account.to_account_info(),
+                    from: ctx.accounts.underwriter_token_account.to_account_info(),
                     to: ctx.accounts.insurance_pool.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
+                    authority: ctx.accounts.underwriter.to_account_info(),
                 },
             ),
-            premium,
+            amount,
         )?;
 
-        // 创建保单
-        let policy = &mut ctx.accounts.policy;
-        let clock = Clock::get()?;
-        
-        policy.owner = ctx.accounts.user.key();
-        policy.product = product_key;
-        policy.coverage_amount = coverage_amount;
-        policy.premium_paid = premium;
-        policy.start_time = clock.unix_timestamp;
-        policy.end_time = clock.unix_timestamp + (duration_days as i64 * 86400);
-        policy.status = PolicyStatus::Active;
-        policy.bump = ctx.bumps.policy;
+        let seeds = &[
+            b"protocol".as_ref(),
+            &[ctx.accounts.protocol.bump],
+        ];
+        let signer = &[&seeds[..]];
 
-        // 更新统计
-        product.total_policies += 1;
-        product.total_coverage = product.total_coverage.checked_add(coverage_amount).unwrap();
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.underwriter_share_account.to_account_info(),
+                    authority: ctx.accounts.protocol.to_account_info(),
+                },
+                signer,
+            ),
+            shares,
+        )?;
+
+        if locked_shares > 0 {
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.share_mint.to_account_info(),
+                        to: ctx.accounts.share_escrow.to_account_info(),
+                        authority: ctx.accounts.protocol.to_account_info(),
+                    },
+                    signer,
+                ),
+                locked_shares,
+            )?;
+        }
+
+        emit!(CapitalDeposited {
+            underwriter: ctx.accounts.underwriter.key(),
+            amount,
+            shares,
+            locked_shares,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         msg!(
-            "Insurance purchased: coverage={}, premium={}, expires={}",
-            coverage_amount,
-            premium,
-            policy.end_time
+            "Capital deposited: amount={}, shares={}, locked_shares={}",
+            amount,
+            shares,
+            locked_shares
         );
         Ok(())
     }
 
-    /// 提交理赔申请
-    pub fn submit_claim(
-        ctx: Context<SubmitClaim>,
-        claim_type: ClaimType,
-        claim_amount: u64,
-        evidence_hash: [u8; 32],
-    ) -> Result<()> {
-        let policy = &ctx.accounts.policy;
-        let clock = Clock::get()?;
+    /// 申请赎回：份额转入托管账户并开始冷却期
+    /// 托管中的份额仍计入份额总量，继续按比例承担冷却期内的赔付，但不再计入承保能力，
+    /// 剩余资本须仍能支撑在保保额
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        require!(!protocol.is_paused(PAUSE_CAPITAL), ErrorCode::ProtocolPaused);
+        require!(shares > 0, ErrorCode::InvalidAmount);
 
-        require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
-        require!(clock.unix_timestamp <= policy.end_time, ErrorCode::PolicyExpired);
-        require!(claim_amount <= policy.coverage_amount, ErrorCode::ClaimExceedsCoverage);
+        protocol.pending_withdrawal_shares = protocol.pending_withdrawal_shares.checked_add(shares).unwrap();
+        let capacity = underwriting_capacity(
+            protocol,
+            ctx.accounts.insurance_pool.amount,
+            ctx.accounts.share_mint.supply,
+        );
+        require!(
+            protocol.active_coverage as u128 <= capacity,
+            ErrorCode::WithdrawalExceedsCapacity
+        );
 
-        let claim = &mut ctx.accounts.claim;
-        claim.policy = ctx.accounts.policy.key();
-        claim.claimant = ctx.accounts.claimant.key();
-        claim.claim_type = claim_type;
-        claim.claim_amount = claim_amount;
-        claim.evidence_hash = evidence_hash;
-        claim.status = ClaimStatus::Pending;
-        claim.submitted_at = clock.unix_timestamp;
-        claim.processed_at = None;
-        claim.payout_amount = None;
-        claim.bump = ctx.bumps.claim;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.underwriter_share_account.to_account_info(),
+                    to: ctx.accounts.share_escrow.to_account_info(),
+                    authority: ctx.accounts.underwriter.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
 
-        msg!("Claim submitted: type={:?}, amount={}", claim_type, claim_amount);
+        // 资金池重新募资前申请的份额已作废
+        let position = &mut ctx.accounts.position;
+        if position.share_epoch != protocol.share_epoch {
+            position.pending_shares = 0;
+            position.share_epoch = protocol.share_epoch;
+        }
+        position.pending_shares = position.pending_shares.checked_add(shares).unwrap();
+        position.withdrawal_requested_at = Clock::get()?.unix_timestamp;
+
+        emit!(WithdrawalRequested {
+            underwriter: position.owner,
+            shares,
+            pending_shares: position.pending_shares,
+            timestamp: position.withdrawal_requested_at,
+        });
+
+        msg!(
+            "Withdrawal requested: shares={}, pending={}",
+            shares,
+            position.pending_shares
+        );
         Ok(())
     }
 
-    /// 处理理赔 (仅限管理员/DAO)
-    pub fn process_claim(
-        ctx: Context<ProcessClaim>,
-        approved: bool,
-        payout_amount: u64,
-    ) -> Result<()> {
-        let claim = &mut ctx.accounts.claim;
-        let policy = &mut ctx.accounts.policy;
+    /// 冷却期结束后的执行期限内赎回资本，按当前资金池净值销毁托管份额
+    /// 逾期未执行的申请失效，需撤回份额后重新申请，避免提前挂单却继续分享保费
+    pub fn withdraw_capital(ctx: Context<WithdrawCapital>) -> Result<()> {
         let protocol = &mut ctx.accounts.protocol;
+        let position = &mut ctx.accounts.position;
         let clock = Clock::get()?;
 
-        require!(claim.status == ClaimStatus::Pending, ErrorCode::ClaimNotPending);
+        require!(!protocol.is_paused(PAUSE_CAPITAL), ErrorCode::ProtocolPaused);
+        let shares = position.pending_shares;
+        require!(shares > 0, ErrorCode::NoPendingWithdrawal);
+        require!(position.share_epoch == protocol.share_epoch, ErrorCode::SharesWrittenOff);
+        require!(
+            clock.unix_timestamp >= position.withdrawal_requested_at + protocol.withdrawal_cooldown,
+            ErrorCode::WithdrawalCooldownActive
+        );
+        require!(
+            clock.unix_timestamp
+                <= position.withdrawal_requested_at + protocol.withdrawal_cooldown + WITHDRAWAL_EXECUTION_WINDOW,
+            ErrorCode::WithdrawalRequestLapsed
+        );
 
-        // 保存用于计算的值
-        let policy_start_time = policy.start_time;
-        let claim_amount = claim.claim_amount;
+        let amount = withdrawal_amount(shares, ctx.accounts.insurance_pool.amount, ctx.accounts.share_mint.supply);
 
-        if approved {
-            require!(payout_amount <= claim_amount, ErrorCode::PayoutExceedsClaim);
+        // 赎回后剩余资本须仍能支撑在保保额
+        position.pending_shares = 0;
+        protocol.pending_withdrawal_shares -= shares;
+        let capacity = underwriting_capacity(
+            protocol,
+            ctx.accounts.insurance_pool.amount - amount,
+            ctx.accounts.share_mint.supply - shares,
+        );
+        require!(
+            protocol.active_coverage as u128 <= capacity,
+            ErrorCode::WithdrawalExceedsCapacity
+        );
 
-            // 如果提供了价格预言机，使用TWAP价格验证（防止闪电贷攻击）
-            if ctx.accounts.price_oracle.is_some() {
-                let twap_price = get_twap_price(
-                    &ctx.accounts.price_oracle.as_ref().unwrap(),
-                    policy_start_time,
-                    clock.unix_timestamp,
-                )?;
-                
-                // 验证价格变化是否合理（防止价格操纵）
-                // 这里可以添加更复杂的验证逻辑
-                msg!("TWAP price verified: {}", twap_price);
-            }
+        // 保存用于 seeds 的值
+        let protocol_bump = protocol.bump;
+        let seeds = &[
+            b"protocol".as_ref(),
+            &[protocol_bump],
+        ];
+        let signer = &[&seeds[..]];
 
-            // 计算实际赔付 (根据赔付率)
-            let product = &ctx.accounts.product;
-            let coverage_rate = product.coverage_rate;
-            let actual_payout = payout_amount
-                .checked_mul(coverage_rate as u64)
-                .unwrap()
-                .checked_div(10000)
-                .unwrap();
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.share_escrow.to_account_info(),
+                    authority: ctx.accounts.protocol.to_account_info(),
+                },
+                signer,
+            ),
+            shares,
+        )?;
 
-            // 保存用于 seeds 的值
-            let protocol_bump = protocol.bump;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.insurance_pool,
+            &ctx.accounts.underwriter_token_account,
+            &ctx.accounts.protocol,
+            protocol_bump,
+            amount,
+        )?;
 
-            // 先更新所有状态，避免借用冲突
-            claim.status = ClaimStatus::Approved;
-            claim.payout_amount = Some(actual_payout);
-            claim.processed_at = Some(clock.unix_timestamp);
-            policy.status = PolicyStatus::Claimed;
-            protocol.total_payouts = protocol.total_payouts.checked_add(actual_payout).unwrap();
-            protocol.total_claims += 1;
+        emit!(CapitalWithdrawn {
+            underwriter: ctx.accounts.underwriter.key(),
+            shares,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
 
-            // 从保险池转账给用户
+        msg!("Capital withdrawn: shares={}, amount={}", shares, amount);
+        Ok(())
+    }
+
+    /// 撤回赎回申请，托管份额退回承保人
+    /// 资金池重新募资前申请的份额已作废，仅清除申请
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        let shares = ctx.accounts.position.pending_shares;
+        require!(shares > 0, ErrorCode::NoPendingWithdrawal);
+
+        if ctx.accounts.position.share_epoch == ctx.accounts.protocol.share_epoch {
             let seeds = &[
                 b"protocol".as_ref(),
-                &[protocol_bump],
+                &[ctx.accounts.protocol.bump],
             ];
             let signer = &[&seeds[..]];
 
@@ -220,262 +434,3873 @@ pub mod cowguard_insurance {
                 CpiContext::new_with_signer(
                     ctx.accounts.token_program.to_account_info(),
                     Transfer {
-                        from: ctx.accounts.insurance_pool.to_account_info(),
-                        to: ctx.accounts.claimant_token_account.to_account_info(),
+                        from: ctx.accounts.share_escrow.to_account_info(),
+                        to: ctx.accounts.underwriter_share_account.to_account_info(),
                         authority: ctx.accounts.protocol.to_account_info(),
                     },
                     signer,
                 ),
-                actual_payout,
+                shares,
             )?;
 
-            msg!("Claim approved: payout={}", actual_payout);
-        } else {
-            claim.status = ClaimStatus::Rejected;
-            claim.processed_at = Some(clock.unix_timestamp);
-            protocol.total_claims += 1;
-            msg!("Claim rejected");
+            let protocol = &mut ctx.accounts.protocol;
+            protocol.pending_withdrawal_shares -= shares;
         }
 
-        Ok(())
-    }
+        let position = &mut ctx.accounts.position;
+        position.pending_shares = 0;
+        position.withdrawal_requested_at = 0;
 
-    /// 取消保单 (仅限保单持有人, 未过期且未理赔)
-    pub fn cancel_policy(ctx: Context<CancelPolicy>) -> Result<()> {
-        let policy = &mut ctx.accounts.policy;
-        let clock = Clock::get()?;
+        emit!(WithdrawalCancelled {
+            underwriter: ctx.accounts.underwriter.key(),
+            shares,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-        require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
-        require!(clock.unix_timestamp <= policy.end_time, ErrorCode::PolicyExpired);
+        msg!("Withdrawal cancelled: shares={}", shares);
+        Ok(())
+    }
 
-        // 计算退款 (按剩余时间比例)
-        let total_duration = policy.end_time - policy.start_time;
-        let elapsed = clock.unix_timestamp - policy.start_time;
-        let remaining_ratio = ((total_duration - elapsed) as u64)
-            .checked_mul(10000)
-            .unwrap()
-            .checked_div(total_duration as u64)
-            .unwrap();
-        
-        let refund = policy.premium_paid
-            .checked_mul(remaining_ratio)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap()
-            .checked_mul(80) // 80% 退款 (20% 手续费)
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
+    /// 创建保险产品
+    pub fn create_product(
+        ctx: Context<CreateProduct>,
+        product_type: InsuranceType,
+        terms: ProductTerms,
+    ) -> Result<()> {
+        terms.validate()?;
 
-        // 保存用于 seeds 的值
-        let protocol_bump = ctx.accounts.protocol.bump;
+        let protocol = &mut ctx.accounts.protocol;
+        let product_id = protocol.product_count;
+        protocol.product_count += 1;
 
-        // 退款
-        let seeds = &[
-            b"protocol".as_ref(),
-            &[protocol_bump],
-        ];
-        let signer = &[&seeds[..]];
+        let registry = &mut ctx.accounts.product_registry;
+        require!(
+            registry.products.len() < MAX_REGISTRY_PRODUCTS,
+            ErrorCode::ProductRegistryFull
+        );
+        registry.products.push(ctx.accounts.product.key());
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.insurance_pool.to_account_info(),
-                    to: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.protocol.to_account_info(),
-                },
-                signer,
-            ),
-            refund,
-        )?;
+        let product = &mut ctx.accounts.product;
+        product.authority = ctx.accounts.authority.key();
+        product.product_id = product_id;
+        product.product_type = product_type;
+        product.terms = terms;
+        product.total_policies = 0;
+        product.total_coverage = 0;
+        product.active_coverage = 0;
+        product.mint_filter = MintFilter::None;
+        product.is_active = true;
+        product.pause_flags = 0;
+        product.bump = ctx.bumps.product;
 
-        policy.status = PolicyStatus::Cancelled;
+        emit!(ProductCreated {
+            product: product.key(),
+            product_id,
+            product_type,
+            authority: product.authority,
+            terms,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-        msg!("Policy cancelled, refund: {}", refund);
+        msg!("Insurance product created: id={}, type={:?}", product_id, product_type);
         Ok(())
     }
 
-    /// 暂停/恢复协议 (仅限管理员)
-    pub fn set_protocol_paused(
-        ctx: Context<UpdateProtocol>,
-        paused: bool,
-    ) -> Result<()> {
-        let protocol = &mut ctx.accounts.protocol;
-        protocol.is_paused = paused;
+    /// 初始化用户保单计数账户 (首次投保前调用)
+    pub fn initialize_user_policies(ctx: Context<InitializeUserPolicies>) -> Result<()> {
+        let user_policies = &mut ctx.accounts.user_policies;
+        user_policies.owner = ctx.accounts.user.key();
+        user_policies.policy_count = 0;
+        user_policies.bump = ctx.bumps.user_policies;
 
-        msg!("Protocol paused: {}", paused);
+        msg!("User policy counter initialized for {}", user_policies.owner);
         Ok(())
     }
 
-    /// 更新产品状态 (仅限管理员)
-    pub fn set_product_active(
-        ctx: Context<UpdateProduct>,
-        active: bool,
+    /// 购买保险
+    pub fn purchase_insurance(
+        ctx: Context<PurchaseInsurance>,
+        coverage_amount: u64,
     ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
         let product = &mut ctx.accounts.product;
-        product.is_active = active;
+        
+        require_not_paused(protocol, product, PAUSE_PURCHASES)?;
+        require!(product.is_active, ErrorCode::ProductInactive);
+        require!(
+            coverage_amount >= product.terms.min_coverage
+                && coverage_amount <= product.terms.max_coverage,
+            ErrorCode::InvalidCoverageAmount
+        );
 
-        msg!("Product active: {}", active);
-        Ok(())
-    }
+        // 产品的可投保代币名单检查
+        let insured_mint = ctx.accounts.insured_mint.key();
+        check_mint_filter(product.mint_filter, &ctx.accounts.mint_entry)?;
 
-    /// 设置价格预言机（仅限管理员）
-    pub fn set_price_oracle(
-        ctx: Context<SetPriceOracle>,
-        token_mint: Pubkey,
-        oracle_account: Pubkey,
-    ) -> Result<()> {
-        let oracle_config = &mut ctx.accounts.oracle_config;
+        // 保存用于计算的值
+        let terms = product.terms;
+        let premium_rate = terms.premium_rate;
+        let duration_days = terms.duration_days;
+        let product_key = product.key();
+        let clock = Clock::get()?;
+
+        // 启用参数化触发的价格下跌险记录投保时的预言机价格，作为参数化赔付的基准
+        let (entry_price, price_oracle) = if product.product_type == InsuranceType::PriceDrop
+            && terms.trigger_threshold > 0
+        {
+            snapshot_entry_price(
+                ctx.accounts.price_oracle.as_ref(),
+                ctx.accounts.oracle_config.as_deref(),
+                clock.unix_timestamp,
+            )?
+        } else {
+            (0, Pubkey::default())
+        };
+
+        // 启用参数化触发的 Rug Pull 险记录投保时 bonding curve 的储备快照；
+        // 未启用时无需 bonding curve，已迁移出 bonding curve 的代币也可投保
+        let (bonding_curve, curve_snapshot) = if product.product_type == InsuranceType::RugPull
+            && terms.trigger_threshold > 0
+        {
+            snapshot_bonding_curve(ctx.accounts.bonding_curve.as_ref(), &insured_mint)?
+        } else {
+            (Pubkey::default(), BondingCurveState::default())
+        };
+
+        // 计算保费，国库费从保费中扣除，其余进入保险池
+        let (premium, treasury_amount, pool_amount) =
+            calculate_premium(coverage_amount, premium_rate, 0, protocol.treasury_fee);
+
+        // 承保能力检查: 在保保额不得超过 承保资本 × 最大杠杆
+        let active_coverage = protocol.active_coverage.checked_add(coverage_amount).unwrap();
+        let capacity = underwriting_capacity(
+            protocol,
+            ctx.accounts.insurance_pool.amount.checked_add(pool_amount).unwrap(),
+            ctx.accounts.share_mint.supply,
+        );
+        require!(active_coverage as u128 <= capacity, ErrorCode::InsufficientCapacity);
+
+        // 转移保费到保险池
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    to: ctx.accounts.insurance_pool.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            pool_amount,
+        )?;
+
+        if treasury_amount > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.user_token_account.to_account_info(),
+                        to: ctx.accounts.treasury_token_account.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                treasury_amount,
+            )?;
+        }
+
+        // 创建保单
+        let policy = &mut ctx.accounts.policy;
         
-        if oracle_config.token_mint == Pubkey::default() {
-            oracle_config.token_mint = token_mint;
-            oracle_config.oracle_account = oracle_account;
-            oracle_config.is_active = true;
-            oracle_config.bump = ctx.bumps.oracle_config;
+        policy.owner = ctx.accounts.user.key();
+        policy.policy_mint = ctx.accounts.policy_mint.key();
+        policy.product = product_key;
+        policy.index = ctx.accounts.user_policies.policy_count;
+        policy.insured_mint = insured_mint;
+        policy.terms = terms;
+        policy.coverage_amount = coverage_amount;
+        policy.remaining_coverage = coverage_amount;
+        policy.claim_count = 0;
+        policy.premium_paid = premium;
+        policy.start_time = clock.unix_timestamp;
+        policy.cover_start = clock.unix_timestamp;
+        policy.end_time = clock.unix_timestamp + (duration_days as i64 * 86400);
+        policy.renewal_start = 0;
+        policy.renewal_premium = 0;
+        policy.renewal_terms = terms;
+        policy.renewal_entry_price = 0;
+        policy.renewal_price_oracle = Pubkey::default();
+        policy.renewal_bonding_curve = Pubkey::default();
+        policy.renewal_real_sol_reserves = 0;
+        policy.status = PolicyStatus::Active;
+        policy.has_pending_claim = false;
+        policy.entry_price = entry_price;
+        policy.price_oracle = price_oracle;
+        policy.bonding_curve = bonding_curve;
+        policy.snapshot_real_sol_reserves = curve_snapshot.real_sol_reserves;
+        policy.rug_pull_observed_slot = 0;
+        policy.bump = ctx.bumps.policy;
+
+        // 铸造 1 枚保单凭证给投保人后撤销铸币权限，持有凭证者即可理赔或退保
+        let seeds = &[
+            b"protocol".as_ref(),
+            &[protocol.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.policy_mint.to_account_info(),
+                    to: ctx.accounts.policy_token_account.to_account_info(),
+                    authority: protocol.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+
+        token::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: protocol.to_account_info(),
+                    account_or_mint: ctx.accounts.policy_mint.to_account_info(),
+                },
+                signer,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        // 更新统计
+        let user_policies = &mut ctx.accounts.user_policies;
+        user_policies.policy_count += 1;
+        product.total_policies += 1;
+        product.total_coverage = product.total_coverage.checked_add(coverage_amount).unwrap();
+        product.active_coverage = product.active_coverage.checked_add(coverage_amount).unwrap();
+        protocol.active_coverage = active_coverage;
+        protocol.total_premium_fees = protocol.total_premium_fees.checked_add(treasury_amount).unwrap();
+
+        emit!(PolicyPurchased {
+            policy: policy.key(),
+            product: product_key,
+            owner: policy.owner,
+            policy_mint: policy.policy_mint,
+            insured_mint,
+            coverage_amount,
+            premium,
+            treasury_fee: treasury_amount,
+            net_to_pool: pool_amount,
+            entry_price,
+            start_time: policy.start_time,
+            end_time: policy.end_time,
+        });
+
+        msg!(
+            "Insurance purchased: coverage={}, premium={}, treasury_fee={}, expires={}",
+            coverage_amount,
+            premium,
+            treasury_amount,
+            policy.end_time
+        );
+        Ok(())
+    }
+
+    /// 续保 (仅限保单持有人)
+    /// 到期前 `RENEWAL_WINDOW` 内按产品当前条款续期一个保险期限，上一期无赔付时保费享受无理赔优惠。
+    /// 新一期自原到期时间起算，届时保额才恢复为投保保额；保障连续，等待期不重新计算；每期只能续保一次
+    pub fn renew_policy(ctx: Context<RenewPolicy>) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        let product = &mut ctx.accounts.product;
+        let policy = &mut ctx.accounts.policy;
+        let clock = Clock::get()?;
+
+        start_renewed_term(protocol, product, policy, clock.unix_timestamp);
+        require_not_paused(protocol, product, PAUSE_PURCHASES)?;
+        require!(product.is_active, ErrorCode::ProductInactive);
+        require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
+        require!(clock.unix_timestamp <= policy.end_time, ErrorCode::PolicyExpired);
+        require!(policy.renewal_start == 0, ErrorCode::RenewalPending);
+        require!(
+            clock.unix_timestamp >= policy.end_time - RENEWAL_WINDOW,
+            ErrorCode::RenewalWindowNotOpen
+        );
+        require!(!policy.has_pending_claim, ErrorCode::ClaimPending);
+        require!(
+            policy.coverage_amount >= product.terms.min_coverage
+                && policy.coverage_amount <= product.terms.max_coverage,
+            ErrorCode::InvalidCoverageAmount
+        );
+        check_mint_filter(product.mint_filter, &ctx.accounts.mint_entry)?;
+
+        let terms = product.terms;
+        let coverage_amount = policy.coverage_amount;
+
+        // 按当前条款为新一期记录参数化赔付基准，新一期开始时才替换当期的基准，未启用触发时清除
+        let (entry_price, price_oracle) = if product.product_type == InsuranceType::PriceDrop
+            && terms.trigger_threshold > 0
+        {
+            snapshot_entry_price(
+                ctx.accounts.price_oracle.as_ref(),
+                ctx.accounts.oracle_config.as_deref(),
+                clock.unix_timestamp,
+            )?
+        } else {
+            (0, Pubkey::default())
+        };
+        let (bonding_curve, curve) = if product.product_type == InsuranceType::RugPull
+            && terms.trigger_threshold > 0
+        {
+            snapshot_bonding_curve(ctx.accounts.bonding_curve.as_ref(), &policy.insured_mint)?
+        } else {
+            (Pubkey::default(), BondingCurveState::default())
+        };
+
+        // 按当前费率计算保费，上一期无赔付 (保额未被消耗) 时享受无理赔优惠
+        let no_claims = policy.remaining_coverage == coverage_amount;
+        let discount = if no_claims { terms.no_claims_discount } else { 0 };
+        let (premium, treasury_amount, pool_amount) =
+            calculate_premium(coverage_amount, terms.premium_rate, discount, protocol.treasury_fee);
+
+        // 新一期将恢复上一期已消耗的保额，需重新检查承保能力
+        let restored_coverage = coverage_amount - policy.remaining_coverage;
+        let active_coverage = protocol.active_coverage.checked_add(restored_coverage).unwrap();
+        let capacity = underwriting_capacity(
+            protocol,
+            ctx.accounts.insurance_pool.amount.checked_add(pool_amount).unwrap(),
+            ctx.accounts.share_mint.supply,
+        );
+        require!(active_coverage as u128 <= capacity, ErrorCode::InsufficientCapacity);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    to: ctx.accounts.insurance_pool.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            pool_amount,
+        )?;
+
+        if treasury_amount > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.user_token_account.to_account_info(),
+                        to: ctx.accounts.treasury_token_account.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                treasury_amount,
+            )?;
+        }
+
+        policy.renewal_start = policy.end_time;
+        policy.renewal_premium = premium;
+        policy.renewal_terms = terms;
+        policy.renewal_entry_price = entry_price;
+        policy.renewal_price_oracle = price_oracle;
+        policy.renewal_bonding_curve = bonding_curve;
+        policy.renewal_real_sol_reserves = curve.real_sol_reserves;
+        policy.end_time += terms.duration_days as i64 * 86400;
+
+        protocol.total_premium_fees = protocol.total_premium_fees.checked_add(treasury_amount).unwrap();
+
+        emit!(PolicyRenewed {
+            policy: policy.key(),
+            holder: ctx.accounts.user.key(),
+            premium,
+            treasury_fee: treasury_amount,
+            net_to_pool: pool_amount,
+            no_claims_discount: discount,
+            restored_coverage,
+            term_start: policy.renewal_start,
+            end_time: policy.end_time,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Policy renewed: premium={}, no_claims_discount={}, starts={}, expires={}",
+            premium,
+            discount,
+            policy.renewal_start,
+            policy.end_time
+        );
+        Ok(())
+    }
+
+    /// 提交理赔申请
+    pub fn submit_claim(
+        ctx: Context<SubmitClaim>,
+        claim_type: ClaimType,
+        claim_amount: u64,
+        evidence_hash: [u8; 32],
+        claim_mode: ClaimMode,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        let policy = &mut ctx.accounts.policy;
+        let clock = Clock::get()?;
+
+        start_renewed_term(protocol, &mut ctx.accounts.product, policy, clock.unix_timestamp);
+        require_not_paused(protocol, &ctx.accounts.product, PAUSE_CLAIMS)?;
+        require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
+        // 等待期内拒绝理赔，防止对已在暴跌的代币临时投保；到期后宽限期内仍可申报保障期内的事件
+        require!(
+            clock.unix_timestamp >= policy.cover_start + policy.terms.waiting_period,
+            ErrorCode::ClaimInWaitingPeriod
+        );
+        require!(
+            clock.unix_timestamp <= policy.end_time + policy.terms.grace_period,
+            ErrorCode::ClaimGracePeriodEnded
+        );
+        require!(!policy.has_pending_claim, ErrorCode::ClaimPending);
+        require!(claim_amount <= policy.remaining_coverage, ErrorCode::ClaimExceedsCoverage);
+        require!(
+            policy.terms.deductible.apply(claim_amount) > 0,
+            ErrorCode::ClaimBelowDeductible
+        );
+
+        // 乐观理赔需缴纳保证金，挑战期内无人质疑即自动批准
+        let (bond_amount, challenge_deadline) = match claim_mode {
+            ClaimMode::Standard => (0, 0),
+            ClaimMode::Optimistic => {
+                require!(protocol.challenge_period > 0, ErrorCode::OptimisticClaimsDisabled);
+                let bond_amount = claim_bond(claim_amount, protocol.claim_bond_rate, protocol.min_claim_bond);
+
+                let claimant_token_account = ctx
+                    .accounts
+                    .claimant_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::BondAccountRequired)?;
+                let bond_vault = ctx
+                    .accounts
+                    .bond_vault
+                    .as_ref()
+                    .ok_or(ErrorCode::BondAccountRequired)?;
+
+                token::transfer(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: claimant_token_account.to_account_info(),
+                            to: bond_vault.to_account_info(),
+                            authority: ctx.accounts.claimant.to_account_info(),
+                        },
+                    ),
+                    bond_amount,
+                )?;
+
+                (bond_amount, clock.unix_timestamp + protocol.challenge_period)
+            }
+        };
+
+        policy.has_pending_claim = true;
+
+        let claim = &mut ctx.accounts.claim;
+        claim.set_inner(InsuranceClaim {
+            mode: claim_mode,
+            bond_amount,
+            challenge_deadline,
+            ..InsuranceClaim::new(
+                policy,
+                ctx.accounts.claimant.key(),
+                claim_type,
+                claim_amount,
+                evidence_hash,
+                clock.unix_timestamp,
+                ctx.bumps.claim,
+            )
+        });
+        policy.claim_count += 1;
+
+        emit!(ClaimSubmitted {
+            claim: claim.key(),
+            policy: claim.policy,
+            claimant: claim.claimant,
+            claim_type,
+            claim_amount,
+            evidence_hash,
+            mode: claim_mode,
+            bond_amount,
+            challenge_deadline,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Claim submitted: type={:?}, amount={}, mode={:?}, bond={}",
+            claim_type,
+            claim_amount,
+            claim_mode,
+            bond_amount
+        );
+        Ok(())
+    }
+
+    /// 处理理赔 (仅限理赔评估人)
+    /// 设立评估委员会后理赔只能由委员会投票裁决
+    pub fn process_claim(
+        ctx: Context<ProcessClaim>,
+        approved: bool,
+        payout_amount: u64,
+    ) -> Result<()> {
+        let claim = &mut ctx.accounts.claim;
+        let policy = &mut ctx.accounts.policy;
+        let protocol = &mut ctx.accounts.protocol;
+        let clock = Clock::get()?;
+
+        require!(claim.is_under_review(), ErrorCode::ClaimNotPending);
+        require!(
+            protocol.assessor_committee == Pubkey::default(),
+            ErrorCode::CommitteeReviewRequired
+        );
+
+        // 保存用于计算的值
+        let claim_amount = claim.claim_amount;
+
+        if approved {
+            require_not_paused(protocol, &ctx.accounts.product, PAUSE_PAYOUTS)?;
+            require!(payout_amount <= claim_amount, ErrorCode::PayoutExceedsClaim);
+
+            // 用预言机平滑价格校验核定赔付（防止闪电贷攻击）
+            verify_oracle_payout(
+                policy,
+                ctx.accounts.product.product_type,
+                ctx.accounts.price_oracle.as_ref(),
+                ctx.accounts.oracle_config.as_deref(),
+                payout_amount,
+                clock.unix_timestamp,
+            )?;
+
+            // 先更新所有状态，避免借用冲突
+            let actual_payout = approve_claim(
+                protocol,
+                &mut ctx.accounts.product,
+                policy,
+                claim,
+                payout_amount,
+                clock.unix_timestamp,
+            );
+
+            // 保存用于 seeds 的值
+            let protocol_bump = protocol.bump;
+
+            // 从保险池转账给用户
+            transfer_from_vault(
+                &ctx.accounts.token_program,
+                &ctx.accounts.insurance_pool,
+                &ctx.accounts.claimant_token_account,
+                &ctx.accounts.protocol,
+                protocol_bump,
+                actual_payout,
+            )?;
+
+            msg!("Claim approved: payout={}", actual_payout);
+        } else {
+            reject_claim(protocol, policy, claim, clock.unix_timestamp);
+            msg!("Claim rejected");
+        }
+
+        if ctx.accounts.claim.mode == ClaimMode::Optimistic {
+            settle_dispute_bonds(
+                &ctx.accounts.token_program,
+                ctx.accounts.bond_vault.as_ref(),
+                &ctx.accounts.claimant_token_account,
+                ctx.accounts.disputer_token_account.as_ref(),
+                &ctx.accounts.protocol,
+                ctx.accounts.claim.bond_amount,
+                approved,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// 初始化理赔评估委员会 (仅限管理员)
+    pub fn initialize_assessor_committee(
+        ctx: Context<InitializeAssessorCommittee>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        validate_committee(&members, threshold)?;
+
+        let committee = &mut ctx.accounts.committee;
+        committee.members = members;
+        committee.threshold = threshold;
+        committee.version = 0;
+        committee.bump = ctx.bumps.committee;
+        ctx.accounts.protocol.assessor_committee = committee.key();
+
+        emit!(AssessorCommitteeUpdated {
+            committee: committee.key(),
+            members: committee.members.clone(),
+            threshold,
+            version: committee.version,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Assessor committee initialized: members={}, threshold={}",
+            committee.members.len(),
+            threshold
+        );
+        Ok(())
+    }
+
+    /// 更新理赔评估委员会成员与门限 (仅限管理员)
+    /// 委员会版本递增，审理中理赔按旧成员投出的票作废，由新成员重新投票
+    pub fn update_assessor_committee(
+        ctx: Context<UpdateAssessorCommittee>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        validate_committee(&members, threshold)?;
+
+        let committee = &mut ctx.accounts.committee;
+        committee.members = members;
+        committee.threshold = threshold;
+        committee.version += 1;
+
+        emit!(AssessorCommitteeUpdated {
+            committee: committee.key(),
+            members: committee.members.clone(),
+            threshold,
+            version: committee.version,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Assessor committee updated: members={}, threshold={}",
+            committee.members.len(),
+            threshold
+        );
+        Ok(())
+    }
+
+    /// 委员会成员对理赔投票
+    /// 批准票达到门限时按批准票提议赔付额的中位数赔付；
+    /// 拒绝票多到批准已不可能达到门限时理赔被拒绝
+    pub fn vote_on_claim(
+        ctx: Context<VoteOnClaim>,
+        approve: bool,
+        proposed_payout: u64,
+    ) -> Result<()> {
+        let committee = &ctx.accounts.committee;
+        let claim = &mut ctx.accounts.claim;
+        let clock = Clock::get()?;
+
+        require!(claim.is_under_review(), ErrorCode::ClaimNotPending);
+        require!(
+            committee.members.contains(&ctx.accounts.assessor.key()),
+            ErrorCode::NotCommitteeMember
+        );
+        if approve {
+            require!(proposed_payout <= claim.claim_amount, ErrorCode::PayoutExceedsClaim);
+        }
+
+        // 委员会变更后清空按旧成员计入的票数
+        if claim.committee_version != committee.version {
+            claim.committee_version = committee.version;
+            claim.approve_votes = 0;
+            claim.reject_votes = 0;
+            claim.proposed_payouts.clear();
+        }
+
+        let vote = &mut ctx.accounts.vote;
+        vote.claim = claim.key();
+        vote.assessor = ctx.accounts.assessor.key();
+        vote.approve = approve;
+        vote.proposed_payout = if approve { proposed_payout } else { 0 };
+        vote.voted_at = clock.unix_timestamp;
+        vote.bump = ctx.bumps.vote;
+
+        if approve {
+            claim.approve_votes += 1;
+            claim.proposed_payouts.push(proposed_payout);
+        } else {
+            claim.reject_votes += 1;
+        }
+
+        emit!(ClaimVoteCast {
+            claim: claim.key(),
+            assessor: vote.assessor,
+            approve,
+            proposed_payout: vote.proposed_payout,
+            approve_votes: claim.approve_votes,
+            reject_votes: claim.reject_votes,
+            committee_version: claim.committee_version,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Claim vote: assessor={}, approve={}, proposed_payout={}",
+            vote.assessor,
+            approve,
+            vote.proposed_payout
+        );
+
+        let threshold = committee.threshold;
+        let max_rejects = committee.members.len() as u8 - threshold;
+
+        if claim.approve_votes >= threshold {
+            require_not_paused(&ctx.accounts.protocol, &ctx.accounts.product, PAUSE_PAYOUTS)?;
+            let mut payouts = claim.proposed_payouts.clone();
+            payouts.sort_unstable();
+            // 偶数票时取较低的中位数
+            let median_payout = payouts[(payouts.len() - 1) / 2];
+            verify_oracle_payout(
+                &ctx.accounts.policy,
+                ctx.accounts.product.product_type,
+                ctx.accounts.price_oracle.as_ref(),
+                ctx.accounts.oracle_config.as_deref(),
+                median_payout,
+                clock.unix_timestamp,
+            )?;
+
+            let actual_payout = approve_claim(
+                &mut ctx.accounts.protocol,
+                &mut ctx.accounts.product,
+                &mut ctx.accounts.policy,
+                claim,
+                median_payout,
+                clock.unix_timestamp,
+            );
+
+            let protocol_bump = ctx.accounts.protocol.bump;
+            transfer_from_vault(
+                &ctx.accounts.token_program,
+                &ctx.accounts.insurance_pool,
+                &ctx.accounts.claimant_token_account,
+                &ctx.accounts.protocol,
+                protocol_bump,
+                actual_payout,
+            )?;
+
+            msg!("Claim approved by committee: payout={}", actual_payout);
+        } else if claim.reject_votes > max_rejects {
+            reject_claim(
+                &mut ctx.accounts.protocol,
+                &mut ctx.accounts.policy,
+                claim,
+                clock.unix_timestamp,
+            );
+            msg!("Claim rejected by committee");
         } else {
-            oracle_config.token_mint = token_mint;
-            oracle_config.oracle_account = oracle_account;
+            return Ok(());
+        }
+
+        if ctx.accounts.claim.mode == ClaimMode::Optimistic {
+            settle_dispute_bonds(
+                &ctx.accounts.token_program,
+                ctx.accounts.bond_vault.as_ref(),
+                &ctx.accounts.claimant_token_account,
+                ctx.accounts.disputer_token_account.as_ref(),
+                &ctx.accounts.protocol,
+                ctx.accounts.claim.bond_amount,
+                ctx.accounts.claim.status == ClaimStatus::Approved,
+            )?;
         }
 
-        msg!("Price oracle set: token={}, oracle={}", token_mint, oracle_account);
-        Ok(())
-    }
+        Ok(())
+    }
+
+    /// 对被拒绝的理赔提出申诉 (仅限申请人)
+    /// 须在拒绝后的申诉期限内提交新证据，由申诉复核人而非原审核人复核
+    pub fn appeal_claim(ctx: Context<AppealClaim>, evidence_hash: [u8; 32]) -> Result<()> {
+        let claim = &mut ctx.accounts.claim;
+        let policy = &mut ctx.accounts.policy;
+        let clock = Clock::get()?;
+
+        require_not_paused(&ctx.accounts.protocol, &ctx.accounts.product, PAUSE_CLAIMS)?;
+        require!(claim.status == ClaimStatus::Rejected, ErrorCode::ClaimNotRejected);
+        require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
+        require!(!policy.has_pending_claim, ErrorCode::ClaimPending);
+        require!(claim.appeal_count < MAX_APPEALS, ErrorCode::AppealLimitReached);
+        let processed_at = claim.processed_at.ok_or(ErrorCode::ClaimNotRejected)?;
+        require!(
+            clock.unix_timestamp <= processed_at + APPEAL_WINDOW,
+            ErrorCode::AppealWindowClosed
+        );
+
+        claim.status = ClaimStatus::Appealed;
+        claim.appeal_count += 1;
+        claim.appeal_evidence.push(evidence_hash);
+        policy.has_pending_claim = true;
+
+        emit!(ClaimAppealed {
+            claim: claim.key(),
+            policy: claim.policy,
+            claimant: claim.claimant,
+            appeal_count: claim.appeal_count,
+            evidence_hash,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Claim appealed: appeal_count={}", claim.appeal_count);
+        Ok(())
+    }
+
+    /// 复核申诉 (仅限申诉复核人)
+    pub fn resolve_appeal(
+        ctx: Context<ResolveAppeal>,
+        approved: bool,
+        payout_amount: u64,
+    ) -> Result<()> {
+        let claim = &mut ctx.accounts.claim;
+        let policy = &mut ctx.accounts.policy;
+        let protocol = &mut ctx.accounts.protocol;
+        let clock = Clock::get()?;
+
+        require!(claim.status == ClaimStatus::Appealed, ErrorCode::ClaimNotAppealed);
+
+        if approved {
+            require_not_paused(protocol, &ctx.accounts.product, PAUSE_PAYOUTS)?;
+            require!(payout_amount <= claim.claim_amount, ErrorCode::PayoutExceedsClaim);
+            verify_oracle_payout(
+                policy,
+                ctx.accounts.product.product_type,
+                ctx.accounts.price_oracle.as_ref(),
+                ctx.accounts.oracle_config.as_deref(),
+                payout_amount,
+                clock.unix_timestamp,
+            )?;
+
+            let actual_payout = approve_claim(
+                protocol,
+                &mut ctx.accounts.product,
+                policy,
+                claim,
+                payout_amount,
+                clock.unix_timestamp,
+            );
+
+            let protocol_bump = protocol.bump;
+            transfer_from_vault(
+                &ctx.accounts.token_program,
+                &ctx.accounts.insurance_pool,
+                &ctx.accounts.claimant_token_account,
+                &ctx.accounts.protocol,
+                protocol_bump,
+                actual_payout,
+            )?;
+
+            msg!("Appeal approved: payout={}", actual_payout);
+        } else {
+            reject_claim(protocol, policy, claim, clock.unix_timestamp);
+            msg!("Appeal rejected");
+        }
+
+        Ok(())
+    }
+
+    /// 设置申诉复核人 (仅限管理员)
+    /// 复核人须独立于管理员和理赔评估人，保证申诉由不同角色复核
+    pub fn set_appeal_reviewer(ctx: Context<UpdateProtocol>, appeal_reviewer: Pubkey) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        require!(
+            appeal_reviewer != protocol.authority && appeal_reviewer != protocol.claims_assessor,
+            ErrorCode::InvalidAppealReviewer
+        );
+        protocol.appeal_reviewer = appeal_reviewer;
+
+        emit!(RoleUpdated {
+            role: ProtocolRole::AppealReviewer,
+            account: appeal_reviewer,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Appeal reviewer set: {}", appeal_reviewer);
+        Ok(())
+    }
+
+    /// 初始化乐观理赔保证金账户并设置参数 (仅限管理员)
+    pub fn initialize_bond_vault(
+        ctx: Context<InitializeBondVault>,
+        challenge_period: i64,      // 挑战期 (秒)
+        claim_bond_rate: u16,       // 保证金率 (基点, 相对理赔金额)
+        min_claim_bond: u64,        // 最低保证金，小额理赔按此收取
+    ) -> Result<()> {
+        validate_optimistic_params(challenge_period, claim_bond_rate, min_claim_bond)?;
+
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.bond_vault = ctx.accounts.bond_vault.key();
+        protocol.challenge_period = challenge_period;
+        protocol.claim_bond_rate = claim_bond_rate;
+        protocol.min_claim_bond = min_claim_bond;
+
+        emit!(OptimisticClaimParamsUpdated {
+            bond_vault: protocol.bond_vault,
+            challenge_period,
+            claim_bond_rate,
+            min_claim_bond,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Bond vault initialized: challenge_period={}, bond_rate={}, min_bond={}",
+            challenge_period,
+            claim_bond_rate,
+            min_claim_bond
+        );
+        Ok(())
+    }
+
+    /// 更新乐观理赔参数 (仅限管理员)
+    /// 只影响之后提交的理赔
+    pub fn set_optimistic_claim_params(
+        ctx: Context<UpdateProtocol>,
+        challenge_period: i64,
+        claim_bond_rate: u16,
+        min_claim_bond: u64,
+    ) -> Result<()> {
+        validate_optimistic_params(challenge_period, claim_bond_rate, min_claim_bond)?;
+
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.challenge_period = challenge_period;
+        protocol.claim_bond_rate = claim_bond_rate;
+        protocol.min_claim_bond = min_claim_bond;
+
+        emit!(OptimisticClaimParamsUpdated {
+            bond_vault: protocol.bond_vault,
+            challenge_period,
+            claim_bond_rate,
+            min_claim_bond,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Optimistic claim params set: challenge_period={}, bond_rate={}, min_bond={}",
+            challenge_period,
+            claim_bond_rate,
+            min_claim_bond
+        );
+        Ok(())
+    }
+
+    /// 在挑战期内质疑乐观理赔，需缴纳与申请人相同的保证金 (任何人可调用)
+    /// 被质疑的理赔转由理赔评估人或评估委员会 (已设立时) 裁决，败方保证金罚没给胜方
+    pub fn dispute_claim(ctx: Context<DisputeClaim>) -> Result<()> {
+        let claim = &mut ctx.accounts.claim;
+        let clock = Clock::get()?;
+
+        // 有意不检查 PAUSE_CLAIMS：finalize 只受 PAUSE_PAYOUTS 约束，
+        // 暂停理赔时若同时禁止质疑，挑战期内的可疑理赔将无人能拦截而自动通过
+        require!(claim.mode == ClaimMode::Optimistic, ErrorCode::NotOptimisticClaim);
+        require!(claim.status == ClaimStatus::Pending, ErrorCode::ClaimNotPending);
+        require!(
+            clock.unix_timestamp <= claim.challenge_deadline,
+            ErrorCode::ChallengePeriodEnded
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.disputer_token_account.to_account_info(),
+                    to: ctx.accounts.bond_vault.to_account_info(),
+                    authority: ctx.accounts.disputer.to_account_info(),
+                },
+            ),
+            claim.bond_amount,
+        )?;
+
+        claim.status = ClaimStatus::Disputed;
+        claim.disputer = ctx.accounts.disputer.key();
+
+        emit!(ClaimDisputed {
+            claim: claim.key(),
+            disputer: claim.disputer,
+            bond_amount: claim.bond_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Claim disputed: disputer={}, bond={}", claim.disputer, claim.bond_amount);
+        Ok(())
+    }
+
+    /// 挑战期结束且无人质疑时批准乐观理赔并退还保证金 (任何人可调用)
+    pub fn finalize_optimistic_claim(ctx: Context<FinalizeOptimisticClaim>) -> Result<()> {
+        let claim = &mut ctx.accounts.claim;
+        let clock = Clock::get()?;
+
+        require_not_paused(&ctx.accounts.protocol, &ctx.accounts.product, PAUSE_PAYOUTS)?;
+        require!(claim.mode == ClaimMode::Optimistic, ErrorCode::NotOptimisticClaim);
+        require!(claim.status == ClaimStatus::Pending, ErrorCode::ClaimNotPending);
+        require!(
+            clock.unix_timestamp > claim.challenge_deadline,
+            ErrorCode::ChallengePeriodActive
+        );
+
+        let claim_amount = claim.claim_amount;
+        let bond_amount = claim.bond_amount;
+        let actual_payout = approve_claim(
+            &mut ctx.accounts.protocol,
+            &mut ctx.accounts.product,
+            &mut ctx.accounts.policy,
+            claim,
+            claim_amount,
+            clock.unix_timestamp,
+        );
+
+        let protocol_bump = ctx.accounts.protocol.bump;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.insurance_pool,
+            &ctx.accounts.claimant_token_account,
+            &ctx.accounts.protocol,
+            protocol_bump,
+            actual_payout,
+        )?;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.bond_vault,
+            &ctx.accounts.claimant_token_account,
+            &ctx.accounts.protocol,
+            protocol_bump,
+            bond_amount,
+        )?;
+
+        msg!("Optimistic claim finalized: payout={}, bond returned={}", actual_payout, bond_amount);
+        Ok(())
+    }
+
+    /// 价格下跌险参数化自动结算 (任何人可调用)
+    /// 当前 EMA 价格相对投保价格的跌幅超过产品阈值时，自动全额赔付
+    /// 只接受投保时记录的价格源；注册表中的价格源更换后，入场价已不可比，需走人工理赔
+    pub fn settle_price_drop_claim(ctx: Context<SettlePriceDropClaim>) -> Result<()> {
+        let product = &mut ctx.accounts.product;
+        let policy = &mut ctx.accounts.policy;
+        let protocol = &mut ctx.accounts.protocol;
+        let clock = Clock::get()?;
+
+        start_renewed_term(protocol, product, policy, clock.unix_timestamp);
+        check_parametric_settlement(protocol, product, policy, InsuranceType::PriceDrop, clock.unix_timestamp)?;
+        require!(policy.entry_price > 0, ErrorCode::InvalidOraclePrice);
+
+        let current_price = get_ema_price(
+            &ctx.accounts.price_oracle,
+            &ctx.accounts.oracle_config,
+            policy.start_time,
+            clock.unix_timestamp,
+        )?;
+
+        // 跌幅 (基点)
+        let price_drop_bps = drop_bps(policy.entry_price, current_price);
+        require!(
+            price_drop_bps >= policy.terms.trigger_threshold as u64,
+            ErrorCode::TriggerThresholdNotMet
+        );
+
+        // 参数化赔付一次性用尽剩余保额
+        let remaining_coverage = policy.remaining_coverage;
+        let claim = &mut ctx.accounts.claim;
+        claim.set_inner(InsuranceClaim::new(
+            policy,
+            ctx.accounts.policy_token_account.owner,
+            ClaimType::PriceDrop,
+            remaining_coverage,
+            ctx.accounts.price_oracle.key().to_bytes(),
+            clock.unix_timestamp,
+            ctx.bumps.claim,
+        ));
+        policy.claim_count += 1;
+        let payout = approve_claim(
+            protocol,
+            product,
+            policy,
+            claim,
+            remaining_coverage,
+            clock.unix_timestamp,
+        );
+
+        // 保存用于 seeds 的值
+        let protocol_bump = protocol.bump;
+
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.insurance_pool,
+            &ctx.accounts.claimant_token_account,
+            &ctx.accounts.protocol,
+            protocol_bump,
+            payout,
+        )?;
+
+        msg!(
+            "Price drop settled: entry={}, current={}, drop_bps={}, payout={}",
+            policy.entry_price,
+            current_price,
+            price_drop_bps,
+            payout
+        );
+        Ok(())
+    }
+
+    /// 观察 Rug Pull 险的 bonding curve 储备 (任何人可调用)
+    /// 真实 SOL 储备跌幅达到阈值时记录首次观察到的 slot，未达到时清除记录。
+    /// 持有人可在单笔交易内卖出、结算再买回，因此结算要求跌幅持续
+    /// `RUG_PULL_CONFIRMATION_SLOTS` 个 slot；期间储备恢复的，任何人都可调用本指令清除记录
+    pub fn observe_rug_pull(ctx: Context<ObserveRugPull>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let clock = Clock::get()?;
+
+        start_renewed_term(
+            &mut ctx.accounts.protocol,
+            &mut ctx.accounts.product,
+            policy,
+            clock.unix_timestamp,
+        );
+
+        require!(policy.terms.trigger_threshold > 0, ErrorCode::ParametricTriggerDisabled);
+        require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
+        require!(clock.unix_timestamp <= policy.end_time, ErrorCode::PolicyExpired);
+        require!(
+            clock.unix_timestamp >= policy.cover_start + policy.terms.waiting_period,
+            ErrorCode::ClaimInWaitingPeriod
+        );
+
+        let curve = BondingCurveState::load(&ctx.accounts.bonding_curve)?;
+        let reserve_drop_bps = curve.real_reserve_drop_bps(policy.snapshot_real_sol_reserves);
+        let triggered = !curve.complete && reserve_drop_bps >= policy.terms.trigger_threshold as u64;
+
+        if !triggered {
+            policy.rug_pull_observed_slot = 0;
+        } else if policy.rug_pull_observed_slot == 0 {
+            policy.rug_pull_observed_slot = clock.slot;
+        }
+
+        emit!(RugPullObserved {
+            policy: policy.key(),
+            real_sol_reserves: curve.real_sol_reserves,
+            drop_bps: reserve_drop_bps,
+            observed_slot: policy.rug_pull_observed_slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!(
+            "Rug pull observed: real_sol={}->{}, drop_bps={}, observed_slot={}",
+            policy.snapshot_real_sol_reserves,
+            curve.real_sol_reserves,
+            reserve_drop_bps,
+            policy.rug_pull_observed_slot
+        );
+        Ok(())
+    }
+
+    /// Rug Pull 险参数化自动结算 (任何人可调用)
+    /// 读取 pump.fun bonding curve，当真实 SOL 储备相对投保快照的跌幅超过产品阈值，
+    /// 且自 `observe_rug_pull` 首次观察起已持续 `RUG_PULL_CONFIRMATION_SLOTS` 个 slot 时自动全额赔付
+    pub fn settle_rug_pull_claim(ctx: Context<SettleRugPullClaim>) -> Result<()> {
+        let product = &mut ctx.accounts.product;
+        let policy = &mut ctx.accounts.policy;
+        let protocol = &mut ctx.accounts.protocol;
+        let clock = Clock::get()?;
+
+        start_renewed_term(protocol, product, policy, clock.unix_timestamp);
+        check_parametric_settlement(protocol, product, policy, InsuranceType::RugPull, clock.unix_timestamp)?;
+
+        // 已迁移到 Raydium 的代币储备会被清空，无法在链上判断，需走人工理赔
+        let curve = BondingCurveState::load(&ctx.accounts.bonding_curve)?;
+        require!(!curve.complete, ErrorCode::BondingCurveComplete);
+
+        let reserve_drop_bps = curve.real_reserve_drop_bps(policy.snapshot_real_sol_reserves);
+        require!(
+            reserve_drop_bps >= policy.terms.trigger_threshold as u64,
+            ErrorCode::TriggerThresholdNotMet
+        );
+        require!(
+            policy.rug_pull_observed_slot != 0
+                && clock.slot >= policy.rug_pull_observed_slot + RUG_PULL_CONFIRMATION_SLOTS,
+            ErrorCode::RugPullNotConfirmed
+        );
+
+        // 参数化赔付一次性用尽剩余保额
+        let remaining_coverage = policy.remaining_coverage;
+        let claim = &mut ctx.accounts.claim;
+        claim.set_inner(InsuranceClaim::new(
+            policy,
+            ctx.accounts.policy_token_account.owner,
+            ClaimType::RugPull,
+            remaining_coverage,
+            ctx.accounts.bonding_curve.key().to_bytes(),
+            clock.unix_timestamp,
+            ctx.bumps.claim,
+        ));
+        policy.claim_count += 1;
+        let payout = approve_claim(
+            protocol,
+            product,
+            policy,
+            claim,
+            remaining_coverage,
+            clock.unix_timestamp,
+        );
+
+        // 保存用于 seeds 的值
+        let protocol_bump = protocol.bump;
+
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.insurance_pool,
+            &ctx.accounts.claimant_token_account,
+            &ctx.accounts.protocol,
+            protocol_bump,
+            payout,
+        )?;
+
+        msg!(
+            "Rug pull settled: real_sol={}->{}, observed_slot={}, drop_bps={}, payout={}",
+            policy.snapshot_real_sol_reserves,
+            curve.real_sol_reserves,
+            policy.rug_pull_observed_slot,
+            reserve_drop_bps,
+            payout
+        );
+        Ok(())
+    }
+
+    /// 取消保单 (仅限保单持有人, 未过期、已过最短持有期且无待处理理赔)
+    pub fn cancel_policy(ctx: Context<CancelPolicy>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let clock = Clock::get()?;
+
+        start_renewed_term(
+            &mut ctx.accounts.protocol,
+            &mut ctx.accounts.product,
+            policy,
+            clock.unix_timestamp,
+        );
+        require_not_paused(&ctx.accounts.protocol, &ctx.accounts.product, PAUSE_CANCELLATIONS)?;
+        check_cancellable(policy, clock.unix_timestamp)?;
+
+        let quote = calculate_refund(policy, ctx.accounts.protocol.treasury_fee, clock.unix_timestamp);
+        let refund = quote.refund;
+        let treasury_amount = quote.treasury_fee;
+
+        // 保存用于 seeds 的值
+        let protocol_bump = ctx.accounts.protocol.bump;
+
+        // 退款
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.insurance_pool,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.protocol,
+            protocol_bump,
+            refund,
+        )?;
+
+        if treasury_amount > 0 {
+            transfer_from_vault(
+                &ctx.accounts.token_program,
+                &ctx.accounts.insurance_pool,
+                &ctx.accounts.treasury_token_account,
+                &ctx.accounts.protocol,
+                protocol_bump,
+                treasury_amount,
+            )?;
+        }
+
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.total_cancellation_fees = protocol
+            .total_cancellation_fees
+            .checked_add(treasury_amount)
+            .unwrap();
+
+        // 销毁保单凭证
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.policy_mint.to_account_info(),
+                    from: ctx.accounts.policy_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        policy.status = PolicyStatus::Cancelled;
+        release_exposure(
+            &mut ctx.accounts.protocol,
+            &mut ctx.accounts.product,
+            policy.remaining_coverage,
+        );
+
+        emit!(PolicyCancelled {
+            policy: policy.key(),
+            holder: ctx.accounts.user.key(),
+            refund,
+            cancellation_fee: quote.cancellation_fee,
+            treasury_fee: treasury_amount,
+            released_coverage: policy.remaining_coverage,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Policy cancelled, refund: {}, treasury_fee: {}", refund, treasury_amount);
+        Ok(())
+    }
+
+    /// 报价: 按产品当前条款计算投保保费 (只读，结果通过 return data 返回)
+    /// 与 `purchase_insurance` 共用计算逻辑，供客户端模拟交易获取
+    pub fn quote_premium(ctx: Context<QuotePremium>, coverage_amount: u64) -> Result<PremiumQuote> {
+        let protocol = &ctx.accounts.protocol;
+        let product = &ctx.accounts.product;
+        let clock = Clock::get()?;
+
+        require_not_paused(protocol, product, PAUSE_PURCHASES)?;
+        require!(product.is_active, ErrorCode::ProductInactive);
+        require!(
+            coverage_amount >= product.terms.min_coverage
+                && coverage_amount <= product.terms.max_coverage,
+            ErrorCode::InvalidCoverageAmount
+        );
+
+        let (premium, treasury_fee, net_to_pool) = calculate_premium(
+            coverage_amount,
+            product.terms.premium_rate,
+            0,
+            protocol.treasury_fee,
+        );
+
+        Ok(PremiumQuote {
+            premium,
+            treasury_fee,
+            net_to_pool,
+            expires_at: clock.unix_timestamp + product.terms.duration_days as i64 * 86400,
+        })
+    }
+
+    /// 报价: 计算当前退保可获得的退款 (只读，结果通过 return data 返回)
+    /// 与 `cancel_policy` 共用计算逻辑
+    pub fn quote_refund(ctx: Context<QuoteRefund>) -> Result<RefundQuote> {
+        let clock = Clock::get()?;
+        // 按新一期已开始后的状态报价，与 cancel_policy 一致
+        let mut policy = ctx.accounts.policy.clone().into_inner();
+        policy.start_renewed_term(clock.unix_timestamp);
+
+        require_not_paused(&ctx.accounts.protocol, &ctx.accounts.product, PAUSE_CANCELLATIONS)?;
+        check_cancellable(&policy, clock.unix_timestamp)?;
+
+        Ok(calculate_refund(&policy, ctx.accounts.protocol.treasury_fee, clock.unix_timestamp))
+    }
+
+    /// 将到期保单标记为过期并释放承保敞口 (任何人可调用)
+    /// `close` 为 true 时同时关闭保单账户，租金退还给保单持有人
+    pub fn expire_policy(ctx: Context<ExpirePolicy>, close: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let clock = Clock::get()?;
+
+        require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
+        require!(
+            clock.unix_timestamp > policy.end_time + policy.terms.grace_period,
+            ErrorCode::PolicyNotExpired
+        );
+        require!(!policy.has_pending_claim, ErrorCode::ClaimPending);
+
+        policy.status = PolicyStatus::Expired;
+        release_exposure(
+            &mut ctx.accounts.protocol,
+            &mut ctx.accounts.product,
+            policy.remaining_coverage,
+        );
+
+        emit!(PolicyExpired {
+            policy: policy.key(),
+            released_coverage: policy.remaining_coverage,
+            closed: close,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Policy expired: {}, closed: {}", policy.key(), close);
+
+        if close {
+            ctx.accounts.policy.close(ctx.accounts.owner.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    /// 关闭已处理完毕的理赔账户，租金退还给申请人 (任何人可调用)
+    /// 保单仍有效时不可关闭，保留理赔记录供申诉与审计
+    pub fn close_claim(ctx: Context<CloseClaim>) -> Result<()> {
+        require!(
+            matches!(
+                ctx.accounts.claim.status,
+                ClaimStatus::Approved | ClaimStatus::Rejected
+            ),
+            ErrorCode::ClaimPending
+        );
+
+        // 保单账户已关闭则无需再检查状态
+        let policy_info = ctx.accounts.policy.to_account_info();
+        if !policy_info.data_is_empty() {
+            require_keys_eq!(*policy_info.owner, crate::ID, ErrorCode::InvalidClaim);
+            let policy = InsurancePolicy::try_deserialize(&mut &policy_info.try_borrow_data()?[..])?;
+            require!(policy.status != PolicyStatus::Active, ErrorCode::PolicyStillActive);
+        }
+
+        msg!("Claim closed: {}", ctx.accounts.claim.key());
+        Ok(())
+    }
+
+    /// 设置协议暂停标志位 (管理员或守护人)
+    /// 守护人只能增加暂停项，解除暂停须由管理员执行
+    pub fn set_pause_flags(ctx: Context<PauseProtocol>, pause_flags: u8) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        let signer = ctx.accounts.signer.key();
+        check_pause_change(protocol, signer, protocol.pause_flags, pause_flags)?;
+        protocol.pause_flags = pause_flags;
+
+        emit!(ProtocolPauseChanged {
+            pause_flags,
+            changed_by: signer,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Protocol pause flags: {:#07b}", pause_flags);
+        Ok(())
+    }
+
+    /// 设置产品暂停标志位 (管理员或守护人)
+    pub fn set_product_pause_flags(ctx: Context<PauseProduct>, pause_flags: u8) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let product = &mut ctx.accounts.product;
+        check_pause_change(&ctx.accounts.protocol, signer, product.pause_flags, pause_flags)?;
+        product.pause_flags = pause_flags;
+
+        emit!(ProductPauseChanged {
+            product: product.key(),
+            pause_flags,
+            changed_by: signer,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Product pause flags: {:#07b}", pause_flags);
+        Ok(())
+    }
+
+    /// 提议转移管理员权限 (仅限管理员)
+    /// 新管理员调用 `accept_authority` 后才生效，提议 `Pubkey::default()` 可撤销
+    pub fn propose_authority(ctx: Context<UpdateProtocol>, new_authority: Pubkey) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.pending_authority = new_authority;
+
+        emit!(AuthorityProposed {
+            authority: protocol.authority,
+            pending_authority: new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Authority proposed: {}", new_authority);
+        Ok(())
+    }
+
+    /// 接受管理员权限 (仅限被提议的新管理员)
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        let previous_authority = protocol.authority;
+        protocol.authority = ctx.accounts.pending_authority.key();
+        protocol.pending_authority = Pubkey::default();
+
+        emit!(AuthorityTransferred {
+            previous_authority,
+            new_authority: protocol.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Authority transferred: {} -> {}", previous_authority, protocol.authority);
+        Ok(())
+    }
+
+    /// 设置守护人 (仅限管理员)，守护人只能暂停协议
+    pub fn set_guardian(ctx: Context<UpdateProtocol>, guardian: Pubkey) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.guardian = guardian;
+
+        emit!(RoleUpdated {
+            role: ProtocolRole::Guardian,
+            account: guardian,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Guardian set: {}", guardian);
+        Ok(())
+    }
+
+    /// 设置理赔评估人 (仅限管理员)，负责审核理赔，须与申诉复核人不同
+    pub fn set_claims_assessor(ctx: Context<UpdateProtocol>, claims_assessor: Pubkey) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        require!(
+            claims_assessor != protocol.appeal_reviewer,
+            ErrorCode::InvalidClaimsAssessor
+        );
+        protocol.claims_assessor = claims_assessor;
+
+        emit!(RoleUpdated {
+            role: ProtocolRole::ClaimsAssessor,
+            account: claims_assessor,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Claims assessor set: {}", claims_assessor);
+        Ok(())
+    }
+
+    /// 排队一项受时间锁保护的管理操作 (仅限管理员)
+    /// 产品条款、预言机、费率的变更须等待对应类别的延迟后才能执行，
+    /// 让保单持有人有时间应对
+    pub fn schedule_admin_action(
+        ctx: Context<ScheduleAdminAction>,
+        action: AdminAction,
+        execute_after: i64,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        let clock = Clock::get()?;
+
+        action.validate()?;
+        require!(
+            execute_after >= clock.unix_timestamp + protocol.timelock_delay(action.class()),
+            ErrorCode::TimelockTooShort
+        );
+
+        let queued_action = &mut ctx.accounts.queued_action;
+        queued_action.id = protocol.queued_action_count;
+        queued_action.proposer = ctx.accounts.authority.key();
+        queued_action.action = action;
+        queued_action.scheduled_at = clock.unix_timestamp;
+        queued_action.execute_after = execute_after;
+        queued_action.bump = ctx.bumps.queued_action;
+        protocol.queued_action_count += 1;
+
+        emit!(AdminActionScheduled {
+            queued_action: queued_action.key(),
+            action,
+            execute_after,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Admin action scheduled: id={}, execute_after={}", queued_action.id, execute_after);
+        Ok(())
+    }
+
+    /// 执行到期的管理操作 (任何人可调用)，执行后关闭排队账户
+    pub fn execute_admin_action(ctx: Context<ExecuteAdminAction>) -> Result<()> {
+        let clock = Clock::get()?;
+        let queued_action_key = ctx.accounts.queued_action.key();
+        let action = ctx.accounts.queued_action.action;
+
+        require!(
+            clock.unix_timestamp >= ctx.accounts.queued_action.execute_after,
+            ErrorCode::TimelockNotExpired
+        );
+
+        match action {
+            // 已售保单保留投保时的条款快照，不受影响
+            AdminAction::UpdateProduct { product, terms } => {
+                let product_account = ctx
+                    .accounts
+                    .product
+                    .as_mut()
+                    .ok_or(ErrorCode::AdminActionAccountMismatch)?;
+                require_keys_eq!(
+                    product_account.key(),
+                    product,
+                    ErrorCode::AdminActionAccountMismatch
+                );
+
+                let old_terms = product_account.terms;
+                product_account.terms = terms;
+
+                emit!(ProductUpdated {
+                    product,
+                    old_terms,
+                    new_terms: terms,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+            AdminAction::UpdatePriceOracle {
+                token_mint,
+                oracle_account,
+                oracle_kind,
+                max_staleness,
+                max_confidence,
+            } => {
+                let oracle_config = ctx
+                    .accounts
+                    .oracle_config
+                    .as_mut()
+                    .ok_or(ErrorCode::AdminActionAccountMismatch)?;
+                require_keys_eq!(
+                    oracle_config.token_mint,
+                    token_mint,
+                    ErrorCode::AdminActionAccountMismatch
+                );
+
+                oracle_config.oracle_account = oracle_account;
+                oracle_config.oracle_kind = oracle_kind;
+                oracle_config.max_staleness = max_staleness;
+                oracle_config.max_confidence = max_confidence;
+                oracle_config.is_active = true;
+                emit_oracle_updated(oracle_config)?;
+            }
+            AdminAction::SetTreasuryFee { treasury_fee } => {
+                ctx.accounts.protocol.treasury_fee = treasury_fee;
+            }
+            AdminAction::SetTimelockDelay { class, delay } => {
+                ctx.accounts.protocol.set_timelock_delay(class, delay);
+            }
+        }
+
+        emit!(AdminActionExecuted {
+            queued_action: queued_action_key,
+            action,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Admin action executed: id={}", ctx.accounts.queued_action.id);
+        Ok(())
+    }
+
+    /// 在执行前取消排队的管理操作 (仅限管理员)
+    pub fn cancel_admin_action(ctx: Context<CancelAdminAction>) -> Result<()> {
+        emit!(AdminActionCancelled {
+            queued_action: ctx.accounts.queued_action.key(),
+            action: ctx.accounts.queued_action.action,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Admin action cancelled: id={}", ctx.accounts.queued_action.id);
+        Ok(())
+    }
+
+    /// 设置最大承保杠杆 (仅限管理员)
+    pub fn set_max_leverage(
+        ctx: Context<UpdateProtocol>,
+        max_leverage: u16,  // 基点, 10000 = 1 倍
+    ) -> Result<()> {
+        require!(
+            max_leverage > 0 && max_leverage <= MAX_LEVERAGE_CAP,
+            ErrorCode::InvalidLeverage
+        );
+
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.max_leverage = max_leverage;
+
+        emit!(MaxLeverageUpdated {
+            max_leverage,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Max leverage set: {}", max_leverage);
+        Ok(())
+    }
+
+    /// 更新产品状态 (仅限管理员)
+    /// 同步更新产品注册表中的在售产品列表
+    pub fn set_product_active(
+        ctx: Context<SetProductActive>,
+        active: bool,
+    ) -> Result<()> {
+        let product_key = ctx.accounts.product.key();
+        let registry = &mut ctx.accounts.product_registry;
+        let listed = registry.products.contains(&product_key);
+
+        if active && !listed {
+            require!(
+                registry.products.len() < MAX_REGISTRY_PRODUCTS,
+                ErrorCode::ProductRegistryFull
+            );
+            registry.products.push(product_key);
+        } else if !active && listed {
+            registry.products.retain(|key| key != &product_key);
+        }
+
+        let product = &mut ctx.accounts.product;
+        product.is_active = active;
+
+        emit!(ProductStatusChanged {
+            product: product_key,
+            is_active: active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Product active: {}", active);
+        Ok(())
+    }
+
+    /// 设置产品的可投保代币名单模式 (仅限管理员)
+    pub fn set_product_mint_filter(
+        ctx: Context<UpdateProduct>,
+        mint_filter: MintFilter,
+    ) -> Result<()> {
+        let product = &mut ctx.accounts.product;
+        product.mint_filter = mint_filter;
+
+        emit!(ProductMintFilterUpdated {
+            product: product.key(),
+            mint_filter,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Product mint filter: {:?}", mint_filter);
+        Ok(())
+    }
+
+    /// 将代币加入产品名单 (仅限管理员)
+    /// 名单含义由产品的 `mint_filter` 决定 (白名单或黑名单)
+    pub fn add_product_mint(ctx: Context<AddProductMint>) -> Result<()> {
+        let mint_entry = &mut ctx.accounts.mint_entry;
+        mint_entry.product = ctx.accounts.product.key();
+        mint_entry.mint = ctx.accounts.mint.key();
+        mint_entry.bump = ctx.bumps.mint_entry;
+
+        emit!(ProductMintListed {
+            product: mint_entry.product,
+            mint: mint_entry.mint,
+            listed: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Product mint added: product={}, mint={}", mint_entry.product, mint_entry.mint);
+        Ok(())
+    }
+
+    /// 将代币移出产品名单 (仅限管理员)
+    pub fn remove_product_mint(ctx: Context<RemoveProductMint>) -> Result<()> {
+        emit!(ProductMintListed {
+            product: ctx.accounts.mint_entry.product,
+            mint: ctx.accounts.mint_entry.mint,
+            listed: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Product mint removed: product={}, mint={}",
+            ctx.accounts.mint_entry.product,
+            ctx.accounts.mint_entry.mint
+        );
+        Ok(())
+    }
+
+    /// 为代币登记价格预言机 (仅限管理员)
+    pub fn add_price_oracle(
+        ctx: Context<AddPriceOracle>,
+        oracle_account: Pubkey,
+        oracle_kind: OracleKind,
+        max_staleness: i64,         // 最大价格延迟 (秒)
+        max_confidence: u16,        // 最大置信区间 (基点, 相对价格)
+    ) -> Result<()> {
+        validate_oracle_params(max_staleness, max_confidence)?;
+
+        let oracle_config = &mut ctx.accounts.oracle_config;
+        oracle_config.token_mint = ctx.accounts.token_mint.key();
+        oracle_config.oracle_account = oracle_account;
+        oracle_config.oracle_kind = oracle_kind;
+        oracle_config.max_staleness = max_staleness;
+        oracle_config.max_confidence = max_confidence;
+        oracle_config.is_active = true;
+        oracle_config.bump = ctx.bumps.oracle_config;
+        emit_oracle_updated(oracle_config)?;
+
+        msg!(
+            "Price oracle added: token={}, oracle={}",
+            oracle_config.token_mint,
+            oracle_account
+        );
+        Ok(())
+    }
+
+    /// 停用代币的价格预言机 (仅限管理员)
+    /// 停用是紧急措施，立即生效，只阻止以该价格源投保、续保及参数化结算；
+    /// 已记录价格源的保单仍可经人工理赔按该价格源核定赔付，停用无法冻结赔付。
+    /// 更换或重新启用预言机须经时间锁
+    pub fn deactivate_price_oracle(ctx: Context<UpdatePriceOracle>) -> Result<()> {
+        let oracle_config = &mut ctx.accounts.oracle_config;
+        oracle_config.is_active = false;
+        emit_oracle_updated(oracle_config)?;
+
+        msg!("Price oracle deactivated: token={}", oracle_config.token_mint);
+        Ok(())
+    }
+}
+
+// ============== 辅助函数 ==============
+
+/// 获取平滑后的预言机价格，防止闪电贷攻击
+///
+/// 预言机必须是代币在注册表中登记且启用的价格源。读取 Pyth v2 价格账户的
+/// EMA 价格 (Pyth 内置的指数移动平均，平滑窗口约一小时)，并非保单窗口内的
+/// TWAP；`[start_time, end_time]` 仅用于要求价格在保单窗口内发布，
+/// 同时延迟与置信区间不超过登记的上限。
+/// 返回值统一换算为 `PRICE_DECIMALS` 位小数。
+fn get_ema_price(
+    oracle_account: &AccountInfo,
+    oracle_config: &OracleConfig,
+    start_time: i64,
+    end_time: i64,
+) -> Result<u128> {
+    require!(oracle_config.is_active, ErrorCode::OracleInactive);
+    require_keys_eq!(
+        oracle_account.key(),
+        oracle_config.oracle_account,
+        ErrorCode::OracleMismatch
+    );
+
+    match oracle_config.oracle_kind {
+        OracleKind::PythV2 => get_pyth_ema_price(oracle_account, oracle_config, start_time, end_time),
+    }
+}
+
+fn get_pyth_ema_price(
+    oracle_account: &AccountInfo,
+    oracle_config: &OracleConfig,
+    start_time: i64,
+    end_time: i64,
+) -> Result<u128> {
+    require_keys_eq!(*oracle_account.owner, PYTH_PROGRAM_ID, ErrorCode::InvalidOracleOwner);
+
+    let data = oracle_account.try_borrow_data()?;
+    let price = PythPrice::parse(&data)?;
+
+    require!(price.status == PYTH_STATUS_TRADING, ErrorCode::OraclePriceUnavailable);
+    require!(
+        price.publish_time >= start_time && price.publish_time <= end_time,
+        ErrorCode::OraclePriceOutsideWindow
+    );
+    require!(
+        end_time - price.publish_time <= oracle_config.max_staleness,
+        ErrorCode::OraclePriceStale
+    );
+    require!(price.ema_price > 0, ErrorCode::InvalidOraclePrice);
+
+    // 置信区间 / 价格 不能超过上限
+    let conf_bps = (price.ema_conf as u128)
+        .checked_mul(10000)
+        .unwrap()
+        .checked_div(price.ema_price as u128)
+        .unwrap();
+    require!(
+        conf_bps <= oracle_config.max_confidence as u128,
+        ErrorCode::OracleConfidenceTooWide
+    );
+
+    msg!(
+        "EMA price: start={}, end={}, publish_time={}",
+        start_time,
+        end_time,
+        price.publish_time
+    );
+
+    scale_price(price.ema_price as u64, price.expo)
+}
+
+fn validate_oracle_params(max_staleness: i64, max_confidence: u16) -> Result<()> {
+    require!(
+        max_staleness > 0 && max_staleness <= MAX_ORACLE_STALENESS,
+        ErrorCode::InvalidOracleParams
+    );
+    require!(
+        max_confidence > 0 && max_confidence <= 10000,
+        ErrorCode::InvalidOracleParams
+    );
+    Ok(())
+}
+
+/// 将 Pyth 价格 (price * 10^expo) 换算为 `PRICE_DECIMALS` 位小数
+/// 换算后为 0 (精度不足以表示) 或超过 `MAX_SCALED_PRICE` 的价格视为无效
+fn scale_price(price: u64, expo: i32) -> Result<u128> {
+    let price = price as u128;
+    let shift = expo + PRICE_DECIMALS;
+    let scaled = if shift >= 0 {
+        10u128
+            .checked_pow(shift as u32)
+            .and_then(|factor| price.checked_mul(factor))
+    } else {
+        10u128
+            .checked_pow(shift.unsigned_abs())
+            .map(|factor| price / factor)
+    };
+    scaled
+        .filter(|scaled| *scaled > 0 && *scaled <= MAX_SCALED_PRICE)
+        .ok_or_else(|| error!(ErrorCode::InvalidOraclePrice))
+}
+
+/// 计算从 `before` 到 `after` 的跌幅 (基点)，`before` 为 0 时视为无跌幅
+fn drop_bps(before: u128, after: u128) -> u64 {
+    if before == 0 {
+        return 0;
+    }
+    before
+        .saturating_sub(after)
+        .checked_mul(10000)
+        .unwrap()
+        .checked_div(before)
+        .unwrap() as u64
+}
+
+/// 参数化结算的共同前置检查：产品类型匹配且启用了触发阈值，
+/// 保单在保障期内、已过等待期且无待处理理赔
+fn check_parametric_settlement(
+    protocol: &InsuranceProtocol,
+    product: &InsuranceProduct,
+    policy: &InsurancePolicy,
+    product_type: InsuranceType,
+    now: i64,
+) -> Result<()> {
+    require_not_paused(protocol, product, PAUSE_PAYOUTS)?;
+    require!(product.product_type == product_type, ErrorCode::InvalidProductType);
+    require!(policy.terms.trigger_threshold > 0, ErrorCode::ParametricTriggerDisabled);
+    require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
+    require!(now <= policy.end_time, ErrorCode::PolicyExpired);
+    require!(
+        now >= policy.cover_start + policy.terms.waiting_period,
+        ErrorCode::ClaimInWaitingPeriod
+    );
+    require!(!policy.has_pending_claim, ErrorCode::ClaimPending);
+    Ok(())
+}
+
+/// 人工批准理赔前用预言机价格校验核定损失
+/// 记录了入场价的价格下跌险保单必须提供投保时记录的价格源，其他保单无从比较，不做校验。
+/// 入场价只能与同一价格源比较，因此注册表中的价格源更换或停用后仍读取保单记录的价格源，
+/// 仅沿用注册表的延迟与置信区间上限，避免人工理赔被价格源变更阻塞
+fn verify_oracle_payout(
+    policy: &InsurancePolicy,
+    product_type: InsuranceType,
+    price_oracle: Option<&AccountInfo>,
+    oracle_config: Option<&OracleConfig>,
+    payout_amount: u64,
+    now: i64,
+) -> Result<()> {
+    if product_type != InsuranceType::PriceDrop || policy.entry_price == 0 {
+        return Ok(());
+    }
+    let price_oracle = price_oracle.ok_or(ErrorCode::OracleRequired)?;
+    let oracle_config = oracle_config.ok_or(ErrorCode::OracleRequired)?;
+    require_keys_eq!(price_oracle.key(), policy.price_oracle, ErrorCode::OracleMismatch);
+
+    let oracle_price = match oracle_config.oracle_kind {
+        OracleKind::PythV2 => get_pyth_ema_price(price_oracle, oracle_config, policy.start_time, now)?,
+    };
+    verify_price_drop_payout(policy, oracle_price, payout_amount)?;
+    msg!("Oracle price verified: {}", oracle_price);
+    Ok(())
+}
+
+/// 用预言机价格校验核定损失：不得超过 保额 × 入场以来的价格跌幅
+/// 保单未记录入场价时无从比较，不做限制
+fn verify_price_drop_payout(policy: &InsurancePolicy, oracle_price: u128, payout_amount: u64) -> Result<()> {
+    if policy.entry_price == 0 {
+        return Ok(());
+    }
+    let max_loss = (policy.coverage_amount as u128)
+        .checked_mul(drop_bps(policy.entry_price, oracle_price) as u128)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap();
+    require!(payout_amount as u128 <= max_loss, ErrorCode::PayoutExceedsPriceDrop);
+    Ok(())
+}
+
+/// 批准理赔并更新理赔、保单与统计状态
+/// 核定损失以剩余保额为上限并从中扣减，保额用尽时保单才终止
+/// 返回扣除免赔额并按投保时赔付率折算后的实际赔付，由调用方从保险池转出
+fn approve_claim(
+    protocol: &mut InsuranceProtocol,
+    product: &mut InsuranceProduct,
+    policy: &mut InsurancePolicy,
+    claim: &mut Account<InsuranceClaim>,
+    payout_amount: u64,
+    now: i64,
+) -> u64 {
+    // 提交后保额可能已被其他理赔 (如申诉通过的旧理赔) 消耗
+    let payout_amount = payout_amount.min(policy.remaining_coverage);
+    let actual_payout = policy
+        .terms
+        .deductible
+        .apply(payout_amount)
+        .checked_mul(policy.terms.coverage_rate as u64)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap();
+
+    claim.status = ClaimStatus::Approved;
+    claim.payout_amount = Some(actual_payout);
+    claim.processed_at = Some(now);
+    policy.has_pending_claim = false;
+
+    policy.remaining_coverage -= payout_amount;
+    release_exposure(protocol, product, payout_amount);
+    if policy.remaining_coverage == 0 {
+        policy.status = PolicyStatus::Claimed;
+    }
+    protocol.total_payouts = protocol.total_payouts.checked_add(actual_payout).unwrap();
+    // 申诉复核不重复计入理赔数
+    if claim.appeal_count == 0 {
+        protocol.total_claims += 1;
+    }
+    emit_claim_processed(claim, policy, now);
+
+    actual_payout
+}
+
+/// 拒绝理赔，保单继续有效
+fn reject_claim(
+    protocol: &mut InsuranceProtocol,
+    policy: &mut InsurancePolicy,
+    claim: &mut Account<InsuranceClaim>,
+    now: i64,
+) {
+    claim.status = ClaimStatus::Rejected;
+    claim.processed_at = Some(now);
+    policy.has_pending_claim = false;
+    if claim.appeal_count == 0 {
+        protocol.total_claims += 1;
+    }
+    emit_claim_processed(claim, policy, now);
+}
+
+/// 发出理赔处理结果事件
+fn emit_claim_processed(claim: &Account<InsuranceClaim>, policy: &InsurancePolicy, now: i64) {
+    emit!(ClaimProcessed {
+        claim: claim.key(),
+        policy: claim.policy,
+        claimant: claim.claimant,
+        status: claim.status,
+        payout_amount: claim.payout_amount.unwrap_or(0),
+        remaining_coverage: policy.remaining_coverage,
+        policy_status: policy.status,
+        timestamp: now,
+    });
+}
+
+/// 发出价格预言机配置变更事件
+fn emit_oracle_updated(oracle_config: &Account<OracleConfig>) -> Result<()> {
+    emit!(PriceOracleUpdated {
+        oracle_config: oracle_config.key(),
+        token_mint: oracle_config.token_mint,
+        oracle_account: oracle_config.oracle_account,
+        oracle_kind: oracle_config.oracle_kind,
+        max_staleness: oracle_config.max_staleness,
+        max_confidence: oracle_config.max_confidence,
+        is_active: oracle_config.is_active,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// 结算被质疑的乐观理赔的双方保证金
+/// 胜方取回自己的保证金，并获得败方被罚没的保证金
+fn settle_dispute_bonds<'info>(
+    token_program: &Program<'info, Token>,
+    bond_vault: Option<&Account<'info, TokenAccount>>,
+    claimant_token_account: &Account<'info, TokenAccount>,
+    disputer_token_account: Option<&Account<'info, TokenAccount>>,
+    protocol: &Account<'info, InsuranceProtocol>,
+    bond_amount: u64,
+    claimant_wins: bool,
+) -> Result<()> {
+    let bond_vault = bond_vault.ok_or(ErrorCode::BondAccountRequired)?;
+    let winner = if claimant_wins {
+        claimant_token_account
+    } else {
+        disputer_token_account.ok_or(ErrorCode::BondAccountRequired)?
+    };
+
+    transfer_from_vault(
+        token_program,
+        bond_vault,
+        winner,
+        protocol,
+        protocol.bump,
+        bond_amount.checked_mul(2).unwrap(),
+    )
+}
+
+fn validate_optimistic_params(challenge_period: i64, claim_bond_rate: u16, min_claim_bond: u64) -> Result<()> {
+    require!(
+        challenge_period > 0 && challenge_period <= MAX_CHALLENGE_PERIOD,
+        ErrorCode::InvalidOptimisticParams
+    );
+    require!(
+        claim_bond_rate > 0 && claim_bond_rate <= 10000,
+        ErrorCode::InvalidOptimisticParams
+    );
+    require!(min_claim_bond > 0, ErrorCode::InvalidOptimisticParams);
+    Ok(())
+}
+
+/// 乐观理赔保证金：理赔金额 × 保证金率，不低于最低保证金，避免小额理赔的质疑与罚没形同虚设
+fn claim_bond(claim_amount: u64, claim_bond_rate: u16, min_claim_bond: u64) -> u64 {
+    claim_amount
+        .checked_mul(claim_bond_rate as u64)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap()
+        .max(min_claim_bond)
+}
+
+fn validate_committee(members: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(
+        !members.is_empty() && members.len() <= MAX_COMMITTEE_MEMBERS,
+        ErrorCode::InvalidCommittee
+    );
+    require!(
+        threshold > 0 && threshold as usize <= members.len(),
+        ErrorCode::InvalidCommittee
+    );
+    let mut sorted = members.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    require!(sorted.len() == members.len(), ErrorCode::InvalidCommittee);
+    Ok(())
+}
+
+/// 保单终止 (理赔、取消、过期) 时释放其占用的承保敞口
+fn release_exposure(
+    protocol: &mut InsuranceProtocol,
+    product: &mut InsuranceProduct,
+    coverage_amount: u64,
+) {
+    protocol.active_coverage = protocol.active_coverage.saturating_sub(coverage_amount);
+    product.active_coverage = product.active_coverage.saturating_sub(coverage_amount);
+}
+
+/// 计算保费及国库费分成，`discount` 为保费优惠 (基点)
+/// 返回 (保费, 国库费, 进入保险池部分)
+fn calculate_premium(
+    coverage_amount: u64,
+    premium_rate: u16,
+    discount: u16,
+    treasury_fee: u16,
+) -> (u64, u64, u64) {
+    let premium = coverage_amount
+        .checked_mul(premium_rate as u64)
+        .unwrap()
+        .checked_mul(10000 - discount as u64)
+        .unwrap()
+        .checked_div(10000 * 10000)
+        .unwrap();
+    let treasury_amount = premium
+        .checked_mul(treasury_fee as u64)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap();
+    (premium, treasury_amount, premium - treasury_amount)
+}
+
+/// 承保能力 = 承保资本 × 最大杠杆
+/// 承保资本为资金池余额中不属于待赎回份额的部分，待赎回资本不再支撑新增保额
+fn underwriting_capacity(protocol: &InsuranceProtocol, pool_balance: u64, share_supply: u64) -> u128 {
+    let capital = if share_supply == 0 {
+        pool_balance as u128
+    } else {
+        (pool_balance as u128)
+            .checked_mul(share_supply.saturating_sub(protocol.pending_withdrawal_shares) as u128)
+            .unwrap()
+            .checked_div(share_supply as u128)
+            .unwrap()
+    };
+    capital
+        .checked_mul(protocol.max_leverage as u128)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap()
+}
+
+/// 计算注资应铸造的份额，返回 (承保人份额, 锁定在托管账户的份额)
+/// 首次注资 1:1，其中 `MIN_LOCKED_SHARES` 永久锁定；注资前资金池已有余额 (如先行收取的保费) 时，
+/// 为其按 1:1 铸造锁定份额，避免首位承保人独占这部分资金。之后按 资金池余额 / 份额总量 定价，
+/// 资金池耗尽后须先 `recapitalize_capital_pool`
+fn deposit_shares(amount: u64, pool_balance: u64, share_supply: u64) -> Result<(u64, u64)> {
+    if share_supply == 0 {
+        require!(amount > MIN_LOCKED_SHARES, ErrorCode::InvalidAmount);
+        let locked_shares = pool_balance.checked_add(MIN_LOCKED_SHARES).unwrap();
+        return Ok((amount - MIN_LOCKED_SHARES, locked_shares));
+    }
+
+    require!(!pool_drained(pool_balance, share_supply), ErrorCode::PoolInsolvent);
+    let shares = (amount as u128)
+        .checked_mul(share_supply as u128)
+        .unwrap()
+        .checked_div(pool_balance as u128)
+        .unwrap();
+    let shares = u64::try_from(shares).map_err(|_| error!(ErrorCode::InvalidAmount))?;
+    require!(shares > 0, ErrorCode::InvalidAmount);
+    Ok((shares, 0))
+}
+
+/// 资金池是否已耗尽: 有流通份额，但每份额净值低于初始 1:1 价格的 1 / `DRAINED_POOL_RATIO`
+fn pool_drained(pool_balance: u64, share_supply: u64) -> bool {
+    share_supply > 0 && (pool_balance as u128) * DRAINED_POOL_RATIO < share_supply as u128
+}
+
+/// 按当前资金池净值计算赎回份额可取回的资金
+fn withdrawal_amount(shares: u64, pool_balance: u64, share_supply: u64) -> u64 {
+    (shares as u128)
+        .checked_mul(pool_balance as u128)
+        .unwrap()
+        .checked_div(share_supply as u128)
+        .unwrap() as u64
+}
+
+/// 续保的新一期已开始时恢复保额，并将恢复的保额计入在保保额
+/// 恢复的保额已在续保时通过承保能力检查
+fn start_renewed_term(
+    protocol: &mut InsuranceProtocol,
+    product: &mut InsuranceProduct,
+    policy: &mut InsurancePolicy,
+    now: i64,
+) {
+    let restored = policy.start_renewed_term(now);
+    product.active_coverage = product.active_coverage.checked_add(restored).unwrap();
+    protocol.active_coverage = protocol.active_coverage.checked_add(restored).unwrap();
+}
+
+/// 检查保单当前是否可退保
+fn check_cancellable(policy: &InsurancePolicy, now: i64) -> Result<()> {
+    require!(policy.status == PolicyStatus::Active, ErrorCode::PolicyNotActive);
+    require!(now <= policy.end_time, ErrorCode::PolicyExpired);
+    require!(!policy.has_pending_claim, ErrorCode::ClaimPending);
+    require!(
+        now >= policy.start_time + policy.terms.min_holding_period,
+        ErrorCode::MinHoldingPeriodActive
+    );
+    Ok(())
+}
+
+/// 按当期剩余时间比例计算退保退款，尚未开始的续保期保费全额计为未赚保费；按条款快照收取退保手续费
+/// 可退部分不超过保费扣除不可退比例后的金额；手续费中按国库费率划给国库，其余留在保险池
+fn calculate_refund(policy: &InsurancePolicy, treasury_fee: u16, now: i64) -> RefundQuote {
+    let term_end = if policy.renewal_start != 0 {
+        policy.renewal_start
+    } else {
+        policy.end_time
+    };
+    let total_duration = term_end - policy.start_time;
+    let elapsed = now - policy.start_time;
+    let remaining_ratio = ((total_duration - elapsed).max(0) as u64)
+        .checked_mul(10000)
+        .unwrap()
+        .checked_div(total_duration as u64)
+        .unwrap();
+
+    let unearned_premium = policy
+        .premium_paid
+        .checked_mul(remaining_ratio)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap()
+        .checked_add(policy.renewal_premium)
+        .unwrap();
+    let refundable_cap = policy
+        .premium_paid
+        .checked_add(policy.renewal_premium)
+        .unwrap()
+        .checked_mul(10000 - policy.terms.non_refundable_rate as u64)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap();
+    let refundable_premium = unearned_premium.min(refundable_cap);
+
+    let cancellation_fee = refundable_premium
+        .checked_mul(policy.terms.cancellation_fee as u64)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap();
+    let refund = refundable_premium - cancellation_fee;
+    let treasury_amount = cancellation_fee
+        .checked_mul(treasury_fee as u64)
+        .unwrap()
+        .checked_div(10000)
+        .unwrap();
+
+    RefundQuote {
+        unearned_premium,
+        refundable_premium,
+        refund,
+        cancellation_fee,
+        treasury_fee: treasury_amount,
+        net_to_pool: cancellation_fee - treasury_amount,
+    }
+}
+
+/// 检查协议及产品均未暂停指定操作
+fn require_not_paused(protocol: &InsuranceProtocol, product: &InsuranceProduct, flag: u8) -> Result<()> {
+    require!(!protocol.is_paused(flag), ErrorCode::ProtocolPaused);
+    require!(product.pause_flags & flag == 0, ErrorCode::ProductPaused);
+    Ok(())
+}
+
+/// 校验暂停标志位变更，守护人只能增加暂停项
+fn check_pause_change(protocol: &InsuranceProtocol, signer: Pubkey, current: u8, new: u8) -> Result<()> {
+    require!(new & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+    if signer != protocol.authority {
+        require!(new & current == current, ErrorCode::Unauthorized);
+    }
+    Ok(())
+}
+
+/// 检查代币是否符合产品的可投保代币名单
+/// `mint_entry` 为名单条目 PDA，账户存在即表示代币在名单中
+fn check_mint_filter(mint_filter: MintFilter, mint_entry: &AccountInfo) -> Result<()> {
+    let listed = mint_entry.owner == &crate::ID && !mint_entry.data_is_empty();
+    match mint_filter {
+        MintFilter::None => {}
+        MintFilter::Allowlist => require!(listed, ErrorCode::MintNotInsurable),
+        MintFilter::Denylist => require!(!listed, ErrorCode::MintNotInsurable),
+    }
+    Ok(())
+}
+
+/// 读取当前预言机价格作为价格下跌险的投保基准价，返回 (价格, 预言机地址)
+/// 基准价为 0 的保单既无法参数化结算，人工赔付也不受价格跌幅约束，因此拒绝投保
+fn snapshot_entry_price(
+    price_oracle: Option<&AccountInfo>,
+    oracle_config: Option<&OracleConfig>,
+    now: i64,
+) -> Result<(u128, Pubkey)> {
+    let oracle = price_oracle.ok_or(ErrorCode::OracleRequired)?;
+    let oracle_config = oracle_config.ok_or(ErrorCode::OracleRequired)?;
+    let price = get_ema_price(oracle, oracle_config, now - oracle_config.max_staleness, now)?;
+    require!(price > 0, ErrorCode::InvalidOraclePrice);
+    Ok((price, oracle.key()))
+}
+
+/// 读取代币的 bonding curve 作为 Rug Pull 险的储备快照，返回 (bonding curve 地址, 状态)
+fn snapshot_bonding_curve(
+    bonding_curve: Option<&AccountInfo>,
+    insured_mint: &Pubkey,
+) -> Result<(Pubkey, BondingCurveState)> {
+    let curve_account = bonding_curve.ok_or(ErrorCode::BondingCurveRequired)?;
+    require_keys_eq!(
+        curve_account.key(),
+        bonding_curve_address(insured_mint),
+        ErrorCode::BondingCurveMismatch
+    );
+    let curve = BondingCurveState::load(curve_account)?;
+    require!(!curve.complete, ErrorCode::BondingCurveComplete);
+    Ok((curve_account.key(), curve))
+}
+
+/// 代币在 pump.fun 上的 bonding curve 地址
+fn bonding_curve_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &PUMP_FUN_PROGRAM_ID).0
+}
+
+/// 以协议 PDA 签名，从协议持有的代币账户 (保险池、保证金账户) 转出代币
+fn transfer_from_vault<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    protocol: &Account<'info, InsuranceProtocol>,
+    protocol_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[
+        b"protocol".as_ref(),
+        &[protocol_bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: vault.to_account_info(),
+                to: to.to_account_info(),
+                authority: protocol.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+    )
+}
+
+/// pump.fun bonding curve 账户中用到的字段
+#[derive(Default)]
+struct BondingCurveState {
+    real_sol_reserves: u64,
+    complete: bool,
+}
+
+impl BondingCurveState {
+    fn load(curve_account: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(
+            *curve_account.owner,
+            PUMP_FUN_PROGRAM_ID,
+            ErrorCode::InvalidBondingCurveOwner
+        );
+
+        let data = curve_account.try_borrow_data()?;
+        require!(
+            data.len() >= BONDING_CURVE_MIN_LEN && data[..8] == BONDING_CURVE_DISCRIMINATOR,
+            ErrorCode::InvalidBondingCurveAccount
+        );
+
+        Ok(Self {
+            real_sol_reserves: read_u64(&data, 32),
+            complete: data[48] != 0,
+        })
+    }
+
+    /// 真实 SOL 储备相对投保快照的跌幅 (基点)
+    /// 虚拟储备含约 30 SOL 的初始值，永远不会低于它，无法体现撤池，因此不作为触发依据
+    fn real_reserve_drop_bps(&self, snapshot_real_sol_reserves: u64) -> u64 {
+        drop_bps(snapshot_real_sol_reserves as u128, self.real_sol_reserves as u128)
+    }
+}
+
+/// Pyth v2 价格账户中用到的字段
+struct PythPrice {
+    expo: i32,
+    ema_price: i64,
+    ema_conf: u64,
+    publish_time: i64,
+    status: u32,
+}
+
+impl PythPrice {
+    fn parse(data: &[u8]) -> Result<Self> {
+        require!(data.len() >= PYTH_PRICE_ACCOUNT_MIN_LEN, ErrorCode::InvalidOracleAccount);
+        require!(
+            read_u32(data, 0) == PYTH_MAGIC
+                && read_u32(data, 4) == PYTH_VERSION_2
+                && read_u32(data, 8) == PYTH_ACCOUNT_TYPE_PRICE,
+            ErrorCode::InvalidOracleAccount
+        );
+
+        Ok(Self {
+            expo: read_u32(data, 20) as i32,
+            ema_price: read_u64(data, 48) as i64,
+            ema_conf: read_u64(data, 72),
+            publish_time: read_u64(data, 96) as i64,
+            status: read_u32(data, 224),
+        })
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+// ============== 账户结构 ==============
+
+#[derive(Accounts)]
+pub struct InitializeProtocol<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InsuranceProtocol::INIT_SPACE,
+        seeds = [b"protocol"],
+        bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProductRegistry::INIT_SPACE,
+        seeds = [b"product_registry"],
+        bump
+    )]
+    pub product_registry: Account<'info, ProductRegistry>,
+
+    /// CHECK: Treasury account
+    pub treasury: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCapitalPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    pub capital_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = capital_mint,
+        token::authority = protocol,
+        seeds = [b"insurance_pool"],
+        bump
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = capital_mint.decimals,
+        mint::authority = protocol,
+        seeds = [b"share_mint"],
+        bump
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = share_mint,
+        token::authority = protocol,
+        seeds = [b"share_escrow"],
+        bump
+    )]
+    pub share_escrow: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(share_epoch: u32)]
+pub struct RecapitalizeCapitalPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool)]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(address = protocol.share_mint @ ErrorCode::InvalidShareAccount)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = share_mint.decimals,
+        mint::authority = protocol,
+        seeds = [b"share_mint", share_epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub new_share_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = new_share_mint,
+        token::authority = protocol,
+        seeds = [b"share_escrow", share_epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub new_share_escrow: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct OpenUnderwriterPosition<'info> {
+    #[account(mut)]
+    pub underwriter: Signer<'info>,
+
+    #[account(
+        init,
+        payer = underwriter,
+        space = 8 + UnderwriterPosition::INIT_SPACE,
+        seeds = [b"underwriter", underwriter.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, UnderwriterPosition>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCapital<'info> {
+    pub underwriter: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.share_mint @ ErrorCode::InvalidShareAccount
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        address = protocol.share_escrow @ ErrorCode::InvalidShareAccount
+    )]
+    pub share_escrow: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub underwriter_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = underwriter_share_account.mint == share_mint.key() @ ErrorCode::InvalidShareAccount
+    )]
+    pub underwriter_share_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    pub underwriter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        seeds = [b"underwriter", underwriter.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, UnderwriterPosition>,
+
+    #[account(mut)]
+    pub underwriter_share_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.share_escrow @ ErrorCode::InvalidShareAccount
+    )]
+    pub share_escrow: Account<'info, TokenAccount>,
+
+    #[account(address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool)]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(address = protocol.share_mint @ ErrorCode::InvalidShareAccount)]
+    pub share_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCapital<'info> {
+    pub underwriter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        seeds = [b"underwriter", underwriter.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, UnderwriterPosition>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.share_mint @ ErrorCode::InvalidShareAccount
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        address = protocol.share_escrow @ ErrorCode::InvalidShareAccount
+    )]
+    pub share_escrow: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub underwriter_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    pub underwriter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        seeds = [b"underwriter", underwriter.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, UnderwriterPosition>,
+
+    #[account(
+        mut,
+        address = protocol.share_escrow @ ErrorCode::InvalidShareAccount
+    )]
+    pub share_escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = underwriter_share_account.mint == protocol.share_mint @ ErrorCode::InvalidShareAccount
+    )]
+    pub underwriter_share_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProduct<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        seeds = [b"product_registry"],
+        bump = product_registry.bump
+    )]
+    pub product_registry: Account<'info, ProductRegistry>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InsuranceProduct::INIT_SPACE,
+        seeds = [b"product", protocol.product_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub product: Account<'info, InsuranceProduct>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUserPolicies<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserPolicies::INIT_SPACE,
+        seeds = [b"user_policies", user.key().as_ref()],
+        bump
+    )]
+    pub user_policies: Account<'info, UserPolicies>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseInsurance<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        seeds = [b"user_policies", user.key().as_ref()],
+        bump = user_policies.bump
+    )]
+    pub user_policies: Account<'info, UserPolicies>,
+
+    pub insured_mint: Account<'info, Mint>,
+
+    /// CHECK: Product mint list entry PDA, may not exist; checked against the product's mint_filter
+    #[account(
+        seeds = [b"mint_entry", product.key().as_ref(), insured_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_entry: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + InsurancePolicy::INIT_SPACE,
+        seeds = [
+            b"policy",
+            user.key().as_ref(),
+            user_policies.policy_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"policy_mint", policy.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = protocol
+    )]
+    pub policy_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = policy_mint,
+        associated_token::authority = user
+    )]
+    pub policy_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(address = protocol.share_mint @ ErrorCode::InvalidShareAccount)]
+    pub share_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == protocol.treasury @ ErrorCode::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == protocol.capital_mint @ ErrorCode::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Pyth price oracle, required for PriceDrop products with a parametric trigger; validated against oracle_config
+    pub price_oracle: Option<AccountInfo<'info>>,
+
+    #[account(
+        seeds = [b"oracle_config", insured_mint.key().as_ref()],
+        bump = oracle_config.bump
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
+
+    /// CHECK: pump.fun bonding curve, required for RugPull products with a parametric trigger
+    pub bonding_curve: Option<AccountInfo<'info>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewPolicy<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        constraint = policy_token_account.mint == policy.policy_mint @ ErrorCode::NotPolicyHolder,
+        constraint = policy_token_account.owner == user.key() @ ErrorCode::NotPolicyHolder,
+        constraint = policy_token_account.amount == 1 @ ErrorCode::NotPolicyHolder
+    )]
+    pub policy_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        address = policy.insured_mint @ ErrorCode::InsuredMintMismatch
+    )]
+    pub insured_mint: Account<'info, Mint>,
+
+    /// CHECK: Product mint list entry PDA, may not exist; checked against the product's mint_filter
+    #[account(
+        seeds = [b"mint_entry", product.key().as_ref(), insured_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_entry: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(address = protocol.share_mint @ ErrorCode::InvalidShareAccount)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == protocol.treasury @ ErrorCode::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == protocol.capital_mint @ ErrorCode::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Pyth price oracle, required for PriceDrop products with a parametric trigger; validated against oracle_config
+    pub price_oracle: Option<AccountInfo<'info>>,
+
+    #[account(
+        seeds = [b"oracle_config", insured_mint.key().as_ref()],
+        bump = oracle_config.bump
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
+
+    /// CHECK: pump.fun bonding curve, required for RugPull products with a parametric trigger
+    pub bonding_curve: Option<AccountInfo<'info>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitClaim<'info> {
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        constraint = policy_token_account.mint == policy.policy_mint @ ErrorCode::NotPolicyHolder,
+        constraint = policy_token_account.owner == claimant.key() @ ErrorCode::NotPolicyHolder,
+        constraint = policy_token_account.amount == 1 @ ErrorCode::NotPolicyHolder
+    )]
+    pub policy_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        address = policy.insured_mint @ ErrorCode::InsuredMintMismatch
+    )]
+    pub insured_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = claimant,
+        space = 8 + InsuranceClaim::INIT_SPACE,
+        seeds = [b"claim", policy.key().as_ref(), &policy.claim_count.to_le_bytes()],
+        bump
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    /// Required for optimistic claims, pays the bond
+    #[account(mut)]
+    pub claimant_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = protocol.bond_vault @ ErrorCode::InvalidBondVault
+    )]
+    pub bond_vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessClaim<'info> {
+    pub assessor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.claims_assessor == assessor.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        mut,
+        constraint = claim.policy == policy.key() @ ErrorCode::InvalidClaim
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == claim.claimant @ ErrorCode::Unauthorized
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.bond_vault @ ErrorCode::InvalidBondVault
+    )]
+    pub bond_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = disputer_token_account.owner == claim.disputer @ ErrorCode::Unauthorized
+    )]
+    pub disputer_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Pyth price oracle, required for PriceDrop policies with an entry price; must be the feed recorded on the policy
+    pub price_oracle: Option<AccountInfo<'info>>,
+
+    #[account(
+        seeds = [b"oracle_config", policy.insured_mint.as_ref()],
+        bump = oracle_config.bump
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePriceDropClaim<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InsuranceClaim::INIT_SPACE,
+        seeds = [b"claim", policy.key().as_ref(), &policy.claim_count.to_le_bytes()],
+        bump
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    /// Current holder's policy token, the payout goes to the holder
+    #[account(
+        constraint = policy_token_account.mint == policy.policy_mint @ ErrorCode::NotPolicyHolder,
+        constraint = policy_token_account.amount == 1 @ ErrorCode::NotPolicyHolder
+    )]
+    pub policy_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == policy_token_account.owner @ ErrorCode::Unauthorized
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Pyth price oracle recorded on the policy at purchase, validated against oracle_config in get_ema_price
+    #[account(address = policy.price_oracle @ ErrorCode::OracleMismatch)]
+    pub price_oracle: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"oracle_config", policy.insured_mint.as_ref()],
+        bump = oracle_config.bump
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ObserveRugPull<'info> {
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct,
+        constraint = policy.bonding_curve == bonding_curve.key() @ ErrorCode::BondingCurveMismatch
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    /// CHECK: pump.fun bonding curve recorded on the policy, validated in BondingCurveState::load
+    pub bonding_curve: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRugPullClaim<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct,
+        constraint = policy.bonding_curve == bonding_curve.key() @ ErrorCode::BondingCurveMismatch
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + InsuranceClaim::INIT_SPACE,
+        seeds = [b"claim", policy.key().as_ref(), &policy.claim_count.to_le_bytes()],
+        bump
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    /// Current holder's policy token, the payout goes to the holder
+    #[account(
+        constraint = policy_token_account.mint == policy.policy_mint @ ErrorCode::NotPolicyHolder,
+        constraint = policy_token_account.amount == 1 @ ErrorCode::NotPolicyHolder
+    )]
+    pub policy_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == policy_token_account.owner @ ErrorCode::Unauthorized
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: pump.fun bonding curve recorded on the policy, validated in BondingCurveState::load
+    pub bonding_curve: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAssessorCommittee<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AssessorCommittee::INIT_SPACE,
+        seeds = [b"assessor_committee"],
+        bump
+    )]
+    pub committee: Account<'info, AssessorCommittee>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAssessorCommittee<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        seeds = [b"assessor_committee"],
+        bump = committee.bump
+    )]
+    pub committee: Account<'info, AssessorCommittee>,
+}
+
+#[derive(Accounts)]
+pub struct VoteOnClaim<'info> {
+    #[account(mut)]
+    pub assessor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        seeds = [b"assessor_committee"],
+        bump = committee.bump
+    )]
+    pub committee: Account<'info, AssessorCommittee>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        mut,
+        constraint = claim.policy == policy.key() @ ErrorCode::InvalidClaim
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    #[account(
+        init,
+        payer = assessor,
+        space = 8 + ClaimVote::INIT_SPACE,
+        seeds = [
+            b"claim_vote",
+            claim.key().as_ref(),
+            assessor.key().as_ref(),
+            &committee.version.to_le_bytes()
+        ],
+        bump
+    )]
+    pub vote: Account<'info, ClaimVote>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == claim.claimant @ ErrorCode::Unauthorized
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.bond_vault @ ErrorCode::InvalidBondVault
+    )]
+    pub bond_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = disputer_token_account.owner == claim.disputer @ ErrorCode::Unauthorized
+    )]
+    pub disputer_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Pyth price oracle, required for PriceDrop policies with an entry price; must be the feed recorded on the policy
+    pub price_oracle: Option<AccountInfo<'info>>,
+
+    #[account(
+        seeds = [b"oracle_config", policy.insured_mint.as_ref()],
+        bump = oracle_config.bump
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AppealClaim<'info> {
+    pub claimant: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        mut,
+        constraint = claim.policy == policy.key() @ ErrorCode::InvalidClaim,
+        constraint = claim.claimant == claimant.key() @ ErrorCode::Unauthorized
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveAppeal<'info> {
+    pub reviewer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.appeal_reviewer == reviewer.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        mut,
+        constraint = claim.policy == policy.key() @ ErrorCode::InvalidClaim
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == claim.claimant @ ErrorCode::Unauthorized
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Pyth price oracle, required for PriceDrop policies with an entry price; must be the feed recorded on the policy
+    pub price_oracle: Option<AccountInfo<'info>>,
+
+    #[account(
+        seeds = [b"oracle_config", policy.insured_mint.as_ref()],
+        bump = oracle_config.bump
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeBondVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        address = protocol.capital_mint @ ErrorCode::InvalidBondVault
+    )]
+    pub capital_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = capital_mint,
+        token::authority = protocol,
+        seeds = [b"bond_vault"],
+        bump
+    )]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeClaim<'info> {
+    pub disputer: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    #[account(mut)]
+    pub disputer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.bond_vault @ ErrorCode::InvalidBondVault
+    )]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeOptimisticClaim<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        mut,
+        constraint = claim.policy == policy.key() @ ErrorCode::InvalidClaim
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.bond_vault @ ErrorCode::InvalidBondVault
+    )]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == claim.claimant @ ErrorCode::Unauthorized
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
 }
 
-// ============== 辅助函数 ==============
+#[derive(Accounts)]
+pub struct CancelPolicy<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
 
-/// 获取TWAP价格（时间加权平均价格）
-/// 防止闪电贷攻击
-fn get_twap_price(
-    oracle_account: &AccountInfo,
-    start_time: i64,
-    end_time: i64,
-) -> Result<u64> {
-    // 这里应该从Pyth Network价格预言机读取价格数据
-    // 由于Solana上Pyth的集成比较复杂，这里提供一个框架
-    
-    // 实际实现需要：
-    // 1. 读取Pyth价格账户
-    // 2. 获取历史价格数据
-    // 3. 计算时间加权平均价格
-    
-    // 简化实现：返回一个模拟价格
-    // 实际部署时需要集成Pyth SDK
-    msg!("TWAP calculation: start={}, end={}", start_time, end_time);
-    
-    // TODO: 集成Pyth Network SDK获取真实TWAP价格
-    // 示例代码框架：
-    // let price_data = pyth_client.get_price_data(oracle_account)?;
-    // let twap = calculate_twap(price_data, start_time, end_time)?;
-    
-    Ok(1000000) // 占位符，实际需要从预言机获取
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
+
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    #[account(
+        mut,
+        address = policy.policy_mint @ ErrorCode::NotPolicyHolder
+    )]
+    pub policy_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = policy_token_account.mint == policy.policy_mint @ ErrorCode::NotPolicyHolder,
+        constraint = policy_token_account.owner == user.key() @ ErrorCode::NotPolicyHolder,
+        constraint = policy_token_account.amount == 1 @ ErrorCode::NotPolicyHolder
+    )]
+    pub policy_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = protocol.insurance_pool @ ErrorCode::InvalidInsurancePool
+    )]
+    pub insurance_pool: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == protocol.treasury @ ErrorCode::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == protocol.capital_mint @ ErrorCode::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
 }
 
-// ============== 账户结构 ==============
+#[derive(Accounts)]
+pub struct QuotePremium<'info> {
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    pub product: Account<'info, InsuranceProduct>,
+}
 
 #[derive(Accounts)]
-pub struct InitializeProtocol<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+pub struct QuoteRefund<'info> {
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    pub product: Account<'info, InsuranceProduct>,
 
     #[account(
-        init,
-        payer = authority,
-        space = 8 + InsuranceProtocol::INIT_SPACE,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+}
+
+#[derive(Accounts)]
+pub struct ExpirePolicy<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
         seeds = [b"protocol"],
-        bump
+        bump = protocol.bump
     )]
     pub protocol: Account<'info, InsuranceProtocol>,
 
-    /// CHECK: Treasury account
-    pub treasury: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub product: Account<'info, InsuranceProduct>,
 
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        constraint = policy.product == product.key() @ ErrorCode::InvalidProduct
+    )]
+    pub policy: Account<'info, InsurancePolicy>,
+
+    /// CHECK: Policy owner, receives the rent when the policy is closed
+    #[account(
+        mut,
+        address = policy.owner @ ErrorCode::Unauthorized
+    )]
+    pub owner: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(product_type: InsuranceType)]
-pub struct CreateProduct<'info> {
+pub struct CloseClaim<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        close = claimant
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    /// CHECK: Policy of the claim, may already be closed; validated in close_claim
+    #[account(
+        address = claim.policy @ ErrorCode::InvalidClaim
+    )]
+    pub policy: UncheckedAccount<'info>,
+
+    /// CHECK: Claimant, receives the rent
+    #[account(
+        mut,
+        address = claim.claimant @ ErrorCode::Unauthorized
+    )]
+    pub claimant: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocol<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [b"protocol"],
         bump = protocol.bump,
         constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
     )]
     pub protocol: Account<'info, InsuranceProtocol>,
+}
+
+#[derive(Accounts)]
+pub struct PauseProtocol<'info> {
+    pub signer: Signer<'info>,
 
     #[account(
-        init,
-        payer = authority,
-        space = 8 + InsuranceProduct::INIT_SPACE,
-        seeds = [b"product", product_type.to_bytes().as_ref()],
-        bump
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == signer.key()
+            || protocol.guardian == signer.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+}
+
+#[derive(Accounts)]
+pub struct PauseProduct<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == signer.key()
+            || protocol.guardian == signer.key() @ ErrorCode::Unauthorized
     )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(mut)]
     pub product: Account<'info, InsuranceProduct>,
+}
 
-    pub system_program: Program<'info, System>,
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub pending_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.pending_authority == pending_authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
 }
 
 #[derive(Accounts)]
-pub struct PurchaseInsurance<'info> {
+pub struct UpdateProduct<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub authority: Signer<'info>,
 
     #[account(
         seeds = [b"protocol"],
-        bump = protocol.bump
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
     )]
     pub protocol: Account<'info, InsuranceProtocol>,
 
     #[account(mut)]
     pub product: Account<'info, InsuranceProduct>,
+}
+
+#[derive(Accounts)]
+pub struct SetProductActive<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
     #[account(
-        init,
-        payer = user,
-        space = 8 + InsurancePolicy::INIT_SPACE,
-        seeds = [b"policy", user.key().as_ref(), product.key().as_ref()],
-        bump
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
     )]
-    pub policy: Account<'info, InsurancePolicy>,
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        seeds = [b"product_registry"],
+        bump = product_registry.bump
+    )]
+    pub product_registry: Account<'info, ProductRegistry>,
 
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub product: Account<'info, InsuranceProduct>,
+}
 
+#[derive(Accounts)]
+pub struct AddProductMint<'info> {
     #[account(mut)]
-    pub insurance_pool: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    pub product: Account<'info, InsuranceProduct>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProductMintEntry::INIT_SPACE,
+        seeds = [b"mint_entry", product.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub mint_entry: Account<'info, ProductMintEntry>,
 
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SubmitClaim<'info> {
+pub struct RemoveProductMint<'info> {
     #[account(mut)]
-    pub claimant: Signer<'info>,
+    pub authority: Signer<'info>,
 
     #[account(
-        constraint = policy.owner == claimant.key() @ ErrorCode::Unauthorized
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
     )]
-    pub policy: Account<'info, InsurancePolicy>,
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"mint_entry", mint_entry.product.as_ref(), mint_entry.mint.as_ref()],
+        bump = mint_entry.bump
+    )]
+    pub mint_entry: Account<'info, ProductMintEntry>,
+}
+
+#[derive(Accounts)]
+pub struct AddPriceOracle<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    pub token_mint: Account<'info, Mint>,
 
     #[account(
         init,
-        payer = claimant,
-        space = 8 + InsuranceClaim::INIT_SPACE,
-        seeds = [b"claim", policy.key().as_ref()],
+        payer = authority,
+        space = 8 + OracleConfig::INIT_SPACE,
+        seeds = [b"oracle_config", token_mint.key().as_ref()],
         bump
     )]
-    pub claim: Account<'info, InsuranceClaim>,
+    pub oracle_config: Account<'info, OracleConfig>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ProcessClaim<'info> {
+pub struct UpdatePriceOracle<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol"],
+        bump = protocol.bump,
+        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub protocol: Account<'info, InsuranceProtocol>,
+
+    #[account(
+        mut,
+        seeds = [b"oracle_config", oracle_config.token_mint.as_ref()],
+        bump = oracle_config.bump
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ScheduleAdminAction<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
@@ -487,32 +4312,24 @@ pub struct ProcessClaim<'info> {
     )]
     pub protocol: Account<'info, InsuranceProtocol>,
 
-    pub product: Account<'info, InsuranceProduct>,
-
-    #[account(mut)]
-    pub policy: Account<'info, InsurancePolicy>,
-
-    #[account(mut)]
-    pub claim: Account<'info, InsuranceClaim>,
-
-    #[account(mut)]
-    pub insurance_pool: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub claimant_token_account: Account<'info, TokenAccount>,
-
-    /// CHECK: Optional Pyth price oracle account
-    pub price_oracle: Option<AccountInfo<'info>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + QueuedAdminAction::INIT_SPACE,
+        seeds = [b"queued_action", protocol.queued_action_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAdminAction>,
 
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CancelPolicy<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+pub struct ExecuteAdminAction<'info> {
+    pub executor: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [b"protocol"],
         bump = protocol.bump
     )]
@@ -520,143 +4337,847 @@ pub struct CancelPolicy<'info> {
 
     #[account(
         mut,
-        constraint = policy.owner == user.key() @ ErrorCode::Unauthorized
+        seeds = [b"queued_action", queued_action.id.to_le_bytes().as_ref()],
+        bump = queued_action.bump,
+        close = proposer
     )]
-    pub policy: Account<'info, InsurancePolicy>,
+    pub queued_action: Account<'info, QueuedAdminAction>,
 
-    #[account(mut)]
-    pub insurance_pool: Account<'info, TokenAccount>,
+    /// CHECK: Receives the queued action rent, must match the proposer
+    #[account(
+        mut,
+        address = queued_action.proposer @ ErrorCode::Unauthorized
+    )]
+    pub proposer: UncheckedAccount<'info>,
 
+    /// Required for UpdateProduct actions
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub product: Option<Account<'info, InsuranceProduct>>,
 
-    pub token_program: Program<'info, Token>,
+    /// Required for UpdatePriceOracle actions
+    #[account(
+        mut,
+        seeds = [b"oracle_config", oracle_config.token_mint.as_ref()],
+        bump = oracle_config.bump
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateProtocol<'info> {
-    #[account(mut)]
+pub struct CancelAdminAction<'info> {
     pub authority: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [b"protocol"],
         bump = protocol.bump,
         constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
     )]
     pub protocol: Account<'info, InsuranceProtocol>,
-}
 
-#[derive(Accounts)]
-pub struct UpdateProduct<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"queued_action", queued_action.id.to_le_bytes().as_ref()],
+        bump = queued_action.bump,
+        close = proposer
+    )]
+    pub queued_action: Account<'info, QueuedAdminAction>,
 
+    /// CHECK: Receives the queued action rent, must match the proposer
     #[account(
-        seeds = [b"protocol"],
-        bump = protocol.bump,
-        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
+        mut,
+        address = queued_action.proposer @ ErrorCode::Unauthorized
     )]
-    pub protocol: Account<'info, InsuranceProtocol>,
+    pub proposer: UncheckedAccount<'info>,
+}
 
-    #[account(mut)]
-    pub product: Account<'info, InsuranceProduct>,
+// ============== 数据结构 ==============
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceProtocol {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub guardian: Pubkey,
+    pub claims_assessor: Pubkey,
+    pub assessor_committee: Pubkey,
+    pub treasury: Pubkey,
+    pub treasury_fee: u16,
+    pub total_policies: u64,
+    pub product_count: u64,
+    pub total_claims: u64,
+    pub total_payouts: u64,
+    pub total_premium_fees: u64,
+    pub total_cancellation_fees: u64,
+    pub pause_flags: u8,
+    pub capital_mint: Pubkey,
+    pub insurance_pool: Pubkey,
+    pub share_mint: Pubkey,
+    pub share_escrow: Pubkey,
+    pub share_epoch: u32,         // 份额代币代数，资金池耗尽重新募资时递增
+    pub withdrawal_cooldown: i64,
+    pub pending_withdrawal_shares: u64,
+    pub active_coverage: u64,
+    pub max_leverage: u16,
+    pub bond_vault: Pubkey,
+    pub challenge_period: i64,
+    pub claim_bond_rate: u16,
+    pub min_claim_bond: u64,
+    pub appeal_reviewer: Pubkey,
+    pub product_timelock: i64,
+    pub oracle_timelock: i64,
+    pub fee_timelock: i64,
+    pub queued_action_count: u64,
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct SetPriceOracle<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+impl InsuranceProtocol {
+    /// 指定操作是否已暂停
+    pub fn is_paused(&self, flag: u8) -> bool {
+        self.pause_flags & flag != 0
+    }
+
+    /// 管理操作类别对应的时间锁延迟 (秒)
+    pub fn timelock_delay(&self, class: AdminActionClass) -> i64 {
+        match class {
+            AdminActionClass::Product => self.product_timelock,
+            AdminActionClass::Oracle => self.oracle_timelock,
+            AdminActionClass::Fee => self.fee_timelock,
+        }
+    }
+
+    pub fn set_timelock_delay(&mut self, class: AdminActionClass, delay: i64) {
+        match class {
+            AdminActionClass::Product => self.product_timelock = delay,
+            AdminActionClass::Oracle => self.oracle_timelock = delay,
+            AdminActionClass::Fee => self.fee_timelock = delay,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceProduct {
+    pub authority: Pubkey,
+    pub product_id: u64,
+    pub product_type: InsuranceType,
+    pub terms: ProductTerms,
+    pub total_policies: u64,
+    pub total_coverage: u64,
+    pub active_coverage: u64,
+    pub mint_filter: MintFilter,
+    pub is_active: bool,
+    pub pause_flags: u8,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProductRegistry {
+    #[max_len(MAX_REGISTRY_PRODUCTS)]
+    pub products: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsurancePolicy {
+    pub owner: Pubkey,       // 投保人 (支付账户租金)，理赔与退保权属于保单凭证持有人
+    pub policy_mint: Pubkey, // 保单凭证 (1-of-1 SPL mint)
+    pub product: Pubkey,
+    pub index: u64,
+    pub insured_mint: Pubkey,
+    pub terms: ProductTerms,
+    pub coverage_amount: u64,
+    pub remaining_coverage: u64,
+    pub claim_count: u64,
+    pub premium_paid: u64,    // 当期保费
+    pub start_time: i64,      // 当期起始时间
+    pub cover_start: i64,     // 连续保障起始时间，等待期自此起算，续保不重置
+    pub end_time: i64,
+    pub renewal_start: i64,   // 已续保但尚未开始的新一期起始时间 (0 = 无)
+    pub renewal_premium: u64, // 新一期已付保费
+    pub renewal_terms: ProductTerms,       // 新一期条款
+    pub renewal_entry_price: u128,         // 新一期投保基准价
+    pub renewal_price_oracle: Pubkey,      // 新一期价格源
+    pub renewal_bonding_curve: Pubkey,     // 新一期 bonding curve
+    pub renewal_real_sol_reserves: u64,    // 新一期真实 SOL 储备快照
+    pub status: PolicyStatus,
+    pub has_pending_claim: bool,
+    pub entry_price: u128,    // 投保基准价 (`PRICE_DECIMALS` 位小数)
+    pub price_oracle: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub snapshot_real_sol_reserves: u64,
+    pub rug_pull_observed_slot: u64, // 储备跌幅首次被观察到的 slot (0 = 未观察到)
+    pub bump: u8,
+}
+
+impl InsurancePolicy {
+    /// 续保的新一期到达起始时间时开始: 保额恢复为投保保额，换用新一期的条款与参数化赔付基准，
+    /// 最短持有期从新一期起算；保障连续，等待期仍从首期起算
+    /// 有待处理理赔时推迟到理赔处理完毕，使其按上一期剩余保额赔付。返回恢复的保额
+    pub fn start_renewed_term(&mut self, now: i64) -> u64 {
+        if self.renewal_start == 0
+            || now < self.renewal_start
+            || self.has_pending_claim
+            || self.status == PolicyStatus::Cancelled
+        {
+            return 0;
+        }
+
+        let restored = self.coverage_amount - self.remaining_coverage;
+        self.remaining_coverage = self.coverage_amount;
+        self.start_time = self.renewal_start;
+        self.premium_paid = self.renewal_premium;
+        self.terms = self.renewal_terms;
+        self.entry_price = self.renewal_entry_price;
+        self.price_oracle = self.renewal_price_oracle;
+        self.bonding_curve = self.renewal_bonding_curve;
+        self.snapshot_real_sol_reserves = self.renewal_real_sol_reserves;
+        self.rug_pull_observed_slot = 0;
+        self.renewal_start = 0;
+        self.renewal_premium = 0;
+        self.renewal_entry_price = 0;
+        self.renewal_price_oracle = Pubkey::default();
+        self.renewal_bonding_curve = Pubkey::default();
+        self.renewal_real_sol_reserves = 0;
+        // 上一期保额用尽的保单随新一期恢复有效
+        if self.status == PolicyStatus::Claimed {
+            self.status = PolicyStatus::Active;
+        }
+        restored
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProductMintEntry {
+    pub product: Pubkey,
+    pub mint: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserPolicies {
+    pub owner: Pubkey,
+    pub policy_count: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceClaim {
+    pub policy: Pubkey,
+    pub index: u64,
+    pub insured_mint: Pubkey,
+    pub claimant: Pubkey,
+    pub claim_type: ClaimType,
+    pub claim_amount: u64,
+    pub evidence_hash: [u8; 32],
+    pub status: ClaimStatus,
+    pub submitted_at: i64,
+    pub processed_at: Option<i64>,
+    pub payout_amount: Option<u64>,
+    pub approve_votes: u8,
+    pub reject_votes: u8,
+    #[max_len(MAX_COMMITTEE_MEMBERS)]
+    pub proposed_payouts: Vec<u64>,
+    pub mode: ClaimMode,
+    pub bond_amount: u64,
+    pub challenge_deadline: i64,
+    pub disputer: Pubkey,
+    pub appeal_count: u8,
+    #[max_len(MAX_APPEALS)]
+    pub appeal_evidence: Vec<[u8; 32]>,
+    pub committee_version: u64, // 计票所依据的委员会版本
+    pub bump: u8,
+}
+
+impl InsuranceClaim {
+    /// 新建待审核的标准理赔，序号取保单当前理赔数
+    pub fn new(
+        policy: &Account<InsurancePolicy>,
+        claimant: Pubkey,
+        claim_type: ClaimType,
+        claim_amount: u64,
+        evidence_hash: [u8; 32],
+        submitted_at: i64,
+        bump: u8,
+    ) -> Self {
+        Self {
+            policy: policy.key(),
+            index: policy.claim_count,
+            insured_mint: policy.insured_mint,
+            claimant,
+            claim_type,
+            claim_amount,
+            evidence_hash,
+            status: ClaimStatus::Pending,
+            submitted_at,
+            processed_at: None,
+            payout_amount: None,
+            approve_votes: 0,
+            reject_votes: 0,
+            proposed_payouts: Vec::new(),
+            mode: ClaimMode::Standard,
+            bond_amount: 0,
+            challenge_deadline: 0,
+            disputer: Pubkey::default(),
+            appeal_count: 0,
+            appeal_evidence: Vec::new(),
+            committee_version: 0,
+            bump,
+        }
+    }
+
+    /// 是否等待理赔评估人或委员会裁决
+    /// 乐观理赔只有被质疑后才进入人工裁决
+    pub fn is_under_review(&self) -> bool {
+        match self.mode {
+            ClaimMode::Standard => self.status == ClaimStatus::Pending,
+            ClaimMode::Optimistic => self.status == ClaimStatus::Disputed,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AssessorCommittee {
+    #[max_len(MAX_COMMITTEE_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub version: u64, // 成员或门限每次变更递增
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimVote {
+    pub claim: Pubkey,
+    pub assessor: Pubkey,
+    pub approve: bool,
+    pub proposed_payout: u64,
+    pub voted_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UnderwriterPosition {
+    pub owner: Pubkey,
+    pub pending_shares: u64,
+    pub withdrawal_requested_at: i64,
+    pub share_epoch: u32,     // 待赎回份额所属的份额代币代数
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OracleConfig {
+    pub token_mint: Pubkey,
+    pub oracle_account: Pubkey,
+    pub oracle_kind: OracleKind,
+    pub max_staleness: i64,
+    pub max_confidence: u16,
+    pub is_active: bool,
+    pub bump: u8,
+}
+
+/// 产品条款，投保时快照到保单上
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct ProductTerms {
+    pub premium_rate: u16,        // 保费率 (基点)
+    pub coverage_rate: u16,       // 赔付率 (基点, 10000 = 100%)
+    pub min_coverage: u64,        // 最小保额
+    pub max_coverage: u64,        // 最大保额
+    pub duration_days: u16,       // 保险期限 (天)
+    pub trigger_threshold: u16,   // 参数化赔付触发阈值 (基点, 0 = 不启用)
+    pub deductible: Deductible,   // 免赔额
+    pub waiting_period: i64,      // 投保后等待期 (秒)，期内不受理理赔
+    pub grace_period: i64,        // 到期后理赔宽限期 (秒)
+    pub no_claims_discount: u16,  // 续保无理赔优惠 (基点)
+    pub cancellation_fee: u16,    // 退保手续费率 (基点, 相对可退保费)
+    pub min_holding_period: i64,  // 最短持有期 (秒)，期内不可退保
+    pub non_refundable_rate: u16, // 不可退保费比例 (基点)
+}
+
+impl ProductTerms {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.premium_rate > 0 && self.premium_rate <= 2000,
+            ErrorCode::InvalidPremiumRate
+        );
+        require!(
+            self.coverage_rate > 0 && self.coverage_rate <= 10000,
+            ErrorCode::InvalidCoverageRate
+        );
+        require!(self.min_coverage < self.max_coverage, ErrorCode::InvalidCoverageRange);
+        require!(self.duration_days > 0, ErrorCode::InvalidDuration);
+        require!(self.trigger_threshold <= 10000, ErrorCode::InvalidTriggerThreshold);
+        require!(
+            self.no_claims_discount <= MAX_NO_CLAIMS_DISCOUNT,
+            ErrorCode::InvalidNoClaimsDiscount
+        );
+        require!(self.cancellation_fee <= 10000, ErrorCode::InvalidCancellationTerms);
+        require!(self.non_refundable_rate <= 10000, ErrorCode::InvalidCancellationTerms);
+        require!(
+            self.min_holding_period >= 0
+                && self.min_holding_period < self.duration_days as i64 * 86400,
+            ErrorCode::InvalidCancellationTerms
+        );
+        if let Deductible::Bps(bps) = self.deductible {
+            require!(bps < 10000, ErrorCode::InvalidDeductible);
+        }
+        require!(
+            self.waiting_period >= 0 && self.waiting_period < self.duration_days as i64 * 86400,
+            ErrorCode::InvalidWaitingPeriod
+        );
+        require!(
+            self.grace_period >= 0 && self.grace_period <= MAX_CLAIM_GRACE_PERIOD,
+            ErrorCode::InvalidGracePeriod
+        );
+        Ok(())
+    }
+}
+
+/// 排队中的管理操作
+#[account]
+#[derive(InitSpace)]
+pub struct QueuedAdminAction {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    pub scheduled_at: i64,
+    pub execute_after: i64,
+    pub bump: u8,
+}
+
+/// 受时间锁保护的管理操作
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum AdminAction {
+    UpdateProduct {
+        product: Pubkey,
+        terms: ProductTerms,
+    },
+    UpdatePriceOracle {
+        token_mint: Pubkey,
+        oracle_account: Pubkey,
+        oracle_kind: OracleKind,
+        max_staleness: i64,
+        max_confidence: u16,
+    },
+    SetTreasuryFee {
+        treasury_fee: u16,
+    },
+    // 修改时间锁本身须经过被修改类别的当前延迟
+    SetTimelockDelay {
+        class: AdminActionClass,
+        delay: i64,
+    },
+}
+
+impl AdminAction {
+    pub fn class(&self) -> AdminActionClass {
+        match *self {
+            AdminAction::UpdateProduct { .. } => AdminActionClass::Product,
+            AdminAction::UpdatePriceOracle { .. } => AdminActionClass::Oracle,
+            AdminAction::SetTreasuryFee { .. } => AdminActionClass::Fee,
+            AdminAction::SetTimelockDelay { class, .. } => class,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            AdminAction::UpdateProduct { terms, .. } => terms.validate(),
+            AdminAction::UpdatePriceOracle {
+                max_staleness,
+                max_confidence,
+                ..
+            } => validate_oracle_params(max_staleness, max_confidence),
+            AdminAction::SetTreasuryFee { treasury_fee } => {
+                require!(treasury_fee <= MAX_TREASURY_FEE, ErrorCode::InvalidTreasuryFee);
+                Ok(())
+            }
+            AdminAction::SetTimelockDelay { delay, .. } => {
+                require!(
+                    (MIN_TIMELOCK_DELAY..=MAX_TIMELOCK_DELAY).contains(&delay),
+                    ErrorCode::InvalidTimelockDelay
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 投保报价
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PremiumQuote {
+    pub premium: u64,      // 保费
+    pub treasury_fee: u64, // 国库费
+    pub net_to_pool: u64,  // 进入保险池部分
+    pub expires_at: i64,   // 按当前时间投保的到期时间
+}
+
+/// 退保报价
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RefundQuote {
+    pub unearned_premium: u64,   // 未赚保费
+    pub refundable_premium: u64, // 扣除不可退部分后的可退保费
+    pub refund: u64,             // 退款
+    pub cancellation_fee: u64,   // 退保手续费
+    pub treasury_fee: u64,       // 手续费中的国库份额
+    pub net_to_pool: u64,        // 手续费中留在保险池部分
+}
+
+/// 免赔额，从核定损失中扣除
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum Deductible {
+    None,
+    Flat(u64), // 固定金额
+    Bps(u16),  // 按损失比例 (基点)
+}
+
+impl Deductible {
+    /// 返回扣除免赔额后的金额
+    pub fn apply(&self, amount: u64) -> u64 {
+        match *self {
+            Deductible::None => amount,
+            Deductible::Flat(flat) => amount.saturating_sub(flat),
+            Deductible::Bps(bps) => amount
+                .checked_mul(10000 - bps as u64)
+                .unwrap()
+                .checked_div(10000)
+                .unwrap(),
+        }
+    }
+}
+
+// ============== 事件 ==============
+
+#[event]
+pub struct ProtocolInitialized {
+    pub protocol: Pubkey,
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub treasury_fee: u16,
+    pub max_leverage: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolPauseChanged {
+    pub pause_flags: u8,
+    pub changed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminActionScheduled {
+    pub queued_action: Pubkey,
+    pub action: AdminAction,
+    pub execute_after: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminActionExecuted {
+    pub queued_action: Pubkey,
+    pub action: AdminAction,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminActionCancelled {
+    pub queued_action: Pubkey,
+    pub action: AdminAction,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityProposed {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleUpdated {
+    pub role: ProtocolRole,
+    pub account: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MaxLeverageUpdated {
+    pub max_leverage: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OptimisticClaimParamsUpdated {
+    pub bond_vault: Pubkey,
+    pub challenge_period: i64,
+    pub claim_bond_rate: u16,
+    pub min_claim_bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssessorCommitteeUpdated {
+    pub committee: Pubkey,
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub version: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapitalPoolInitialized {
+    pub capital_mint: Pubkey,
+    pub insurance_pool: Pubkey,
+    pub share_mint: Pubkey,
+    pub withdrawal_cooldown: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapitalPoolRecapitalized {
+    pub share_epoch: u32,
+    pub share_mint: Pubkey,
+    pub written_off_shares: u64,
+    pub residual_balance: u64,
+    pub timestamp: i64,
+}
 
-    #[account(
-        seeds = [b"protocol"],
-        bump = protocol.bump,
-        constraint = protocol.authority == authority.key() @ ErrorCode::Unauthorized
-    )]
-    pub protocol: Account<'info, InsuranceProtocol>,
+#[event]
+pub struct CapitalDeposited {
+    pub underwriter: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub locked_shares: u64,
+    pub timestamp: i64,
+}
 
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + OracleConfig::INIT_SPACE,
-        seeds = [b"oracle_config"],
-        bump
-    )]
-    pub oracle_config: Account<'info, OracleConfig>,
+#[event]
+pub struct WithdrawalRequested {
+    pub underwriter: Pubkey,
+    pub shares: u64,
+    pub pending_shares: u64,
+    pub timestamp: i64,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct CapitalWithdrawn {
+    pub underwriter: Pubkey,
+    pub shares: u64,
+    pub amount: u64,
+    pub timestamp: i64,
 }
 
-// ============== 数据结构 ==============
+#[event]
+pub struct WithdrawalCancelled {
+    pub underwriter: Pubkey,
+    pub shares: u64,
+    pub timestamp: i64,
+}
 
-#[account]
-#[derive(InitSpace)]
-pub struct InsuranceProtocol {
+#[event]
+pub struct ProductCreated {
+    pub product: Pubkey,
+    pub product_id: u64,
+    pub product_type: InsuranceType,
     pub authority: Pubkey,
-    pub treasury: Pubkey,
-    pub treasury_fee: u16,
-    pub total_policies: u64,
-    pub total_claims: u64,
-    pub total_payouts: u64,
-    pub is_paused: bool,
-    pub bump: u8,
+    pub terms: ProductTerms,
+    pub timestamp: i64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct InsuranceProduct {
-    pub authority: Pubkey,
-    pub product_type: InsuranceType,
-    pub premium_rate: u16,
-    pub coverage_rate: u16,
-    pub min_coverage: u64,
-    pub max_coverage: u64,
-    pub duration_days: u16,
-    pub total_policies: u64,
-    pub total_coverage: u64,
+#[event]
+pub struct ProductUpdated {
+    pub product: Pubkey,
+    pub old_terms: ProductTerms,
+    pub new_terms: ProductTerms,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProductStatusChanged {
+    pub product: Pubkey,
     pub is_active: bool,
-    pub bump: u8,
+    pub timestamp: i64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct InsurancePolicy {
-    pub owner: Pubkey,
+#[event]
+pub struct ProductPauseChanged {
+    pub product: Pubkey,
+    pub pause_flags: u8,
+    pub changed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProductMintFilterUpdated {
+    pub product: Pubkey,
+    pub mint_filter: MintFilter,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProductMintListed {
+    pub product: Pubkey,
+    pub mint: Pubkey,
+    pub listed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PolicyPurchased {
+    pub policy: Pubkey,
     pub product: Pubkey,
+    pub owner: Pubkey,
+    pub policy_mint: Pubkey,
+    pub insured_mint: Pubkey,
     pub coverage_amount: u64,
-    pub premium_paid: u64,
+    pub premium: u64,
+    pub treasury_fee: u64,
+    pub net_to_pool: u64,
+    pub entry_price: u128,
     pub start_time: i64,
     pub end_time: i64,
-    pub status: PolicyStatus,
-    pub bump: u8,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct InsuranceClaim {
+#[event]
+pub struct PolicyRenewed {
+    pub policy: Pubkey,
+    pub holder: Pubkey,
+    pub premium: u64,
+    pub treasury_fee: u64,
+    pub net_to_pool: u64,
+    pub no_claims_discount: u16,
+    pub restored_coverage: u64,
+    pub term_start: i64,
+    pub end_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PolicyCancelled {
+    pub policy: Pubkey,
+    pub holder: Pubkey,
+    pub refund: u64,
+    pub cancellation_fee: u64,
+    pub treasury_fee: u64,
+    pub released_coverage: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PolicyExpired {
+    pub policy: Pubkey,
+    pub released_coverage: u64,
+    pub closed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimSubmitted {
+    pub claim: Pubkey,
     pub policy: Pubkey,
     pub claimant: Pubkey,
     pub claim_type: ClaimType,
     pub claim_amount: u64,
     pub evidence_hash: [u8; 32],
+    pub mode: ClaimMode,
+    pub bond_amount: u64,
+    pub challenge_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimProcessed {
+    pub claim: Pubkey,
+    pub policy: Pubkey,
+    pub claimant: Pubkey,
     pub status: ClaimStatus,
-    pub submitted_at: i64,
-    pub processed_at: Option<i64>,
-    pub payout_amount: Option<u64>,
-    pub bump: u8,
+    pub payout_amount: u64,
+    pub remaining_coverage: u64,
+    pub policy_status: PolicyStatus,
+    pub timestamp: i64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct OracleConfig {
+#[event]
+pub struct ClaimVoteCast {
+    pub claim: Pubkey,
+    pub assessor: Pubkey,
+    pub approve: bool,
+    pub proposed_payout: u64,
+    pub approve_votes: u8,
+    pub reject_votes: u8,
+    pub committee_version: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimDisputed {
+    pub claim: Pubkey,
+    pub disputer: Pubkey,
+    pub bond_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimAppealed {
+    pub claim: Pubkey,
+    pub policy: Pubkey,
+    pub claimant: Pubkey,
+    pub appeal_count: u8,
+    pub evidence_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RugPullObserved {
+    pub policy: Pubkey,
+    pub real_sol_reserves: u64,
+    pub drop_bps: u64,
+    pub observed_slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceOracleUpdated {
+    pub oracle_config: Pubkey,
     pub token_mint: Pubkey,
     pub oracle_account: Pubkey,
+    pub oracle_kind: OracleKind,
+    pub max_staleness: i64,
+    pub max_confidence: u16,
     pub is_active: bool,
-    pub bump: u8,
+    pub timestamp: i64,
 }
 
 // ============== 枚举类型 ==============
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum AdminActionClass {
+    Product,    // 产品条款
+    Oracle,     // 价格预言机
+    Fee,        // 协议费率
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum ProtocolRole {
+    Guardian,       // 守护人
+    ClaimsAssessor, // 理赔评估人
+    AppealReviewer, // 申诉复核人
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
 pub enum InsuranceType {
     RugPull,        // Rug Pull 保险
@@ -676,6 +5197,18 @@ impl InsuranceType {
     }
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum OracleKind {
+    PythV2,         // Pyth v2 推送价格账户
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum MintFilter {
+    None,           // 不限制投保代币
+    Allowlist,      // 仅名单内代币可投保
+    Denylist,       // 名单内代币不可投保
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
 pub enum PolicyStatus {
     Active,
@@ -697,6 +5230,14 @@ pub enum ClaimStatus {
     Pending,
     Approved,
     Rejected,
+    Disputed,
+    Appealed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum ClaimMode {
+    Standard,       // 人工审核
+    Optimistic,     // 缴纳保证金，挑战期后自动批准
 }
 
 // ============== 错误码 ==============
@@ -727,4 +5268,841 @@ pub enum ErrorCode {
     ClaimNotPending,
     #[msg("Payout exceeds claim amount")]
     PayoutExceedsClaim,
+    #[msg("Oracle account is not owned by the Pyth program")]
+    InvalidOracleOwner,
+    #[msg("Oracle account is not a valid Pyth price account")]
+    InvalidOracleAccount,
+    #[msg("Oracle price is not currently trading")]
+    OraclePriceUnavailable,
+    #[msg("Oracle price was not published within the policy window")]
+    OraclePriceOutsideWindow,
+    #[msg("Oracle price is stale")]
+    OraclePriceStale,
+    #[msg("Oracle price confidence interval is too wide")]
+    OracleConfidenceTooWide,
+    #[msg("Invalid oracle price")]
+    InvalidOraclePrice,
+    #[msg("Invalid trigger threshold")]
+    InvalidTriggerThreshold,
+    #[msg("Price oracle account is required")]
+    OracleRequired,
+    #[msg("Price oracle does not match the registered feed")]
+    OracleMismatch,
+    #[msg("Invalid product type for this instruction")]
+    InvalidProductType,
+    #[msg("Policy does not belong to this product")]
+    InvalidProduct,
+    #[msg("Parametric trigger is disabled for this product")]
+    ParametricTriggerDisabled,
+    #[msg("Trigger threshold not met")]
+    TriggerThresholdNotMet,
+    #[msg("Bonding curve account is required")]
+    BondingCurveRequired,
+    #[msg("Bonding curve is not owned by the pump.fun program")]
+    InvalidBondingCurveOwner,
+    #[msg("Invalid bonding curve account")]
+    InvalidBondingCurveAccount,
+    #[msg("Bonding curve does not match the policy")]
+    BondingCurveMismatch,
+    #[msg("Bonding curve is complete")]
+    BondingCurveComplete,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Invalid withdrawal cooldown")]
+    InvalidWithdrawalCooldown,
+    #[msg("Insurance pool account does not match the protocol")]
+    InvalidInsurancePool,
+    #[msg("Share mint or escrow does not match the protocol")]
+    InvalidShareAccount,
+    #[msg("Insurance pool is drained; recapitalize it before depositing")]
+    PoolInsolvent,
+    #[msg("Insurance pool still backs its outstanding shares")]
+    PoolNotDrained,
+    #[msg("Share epoch must follow the current one")]
+    InvalidShareEpoch,
+    #[msg("Pending shares were written off when the capital pool was recapitalized")]
+    SharesWrittenOff,
+    #[msg("No pending withdrawal")]
+    NoPendingWithdrawal,
+    #[msg("Withdrawal cooldown has not elapsed")]
+    WithdrawalCooldownActive,
+    #[msg("Withdrawal request has lapsed")]
+    WithdrawalRequestLapsed,
+    #[msg("Insufficient pool capacity for this coverage")]
+    InsufficientCapacity,
+    #[msg("Withdrawal would leave active coverage above pool capacity")]
+    WithdrawalExceedsCapacity,
+    #[msg("Invalid max leverage")]
+    InvalidLeverage,
+    #[msg("Claim does not belong to this policy")]
+    InvalidClaim,
+    #[msg("Invalid treasury fee")]
+    InvalidTreasuryFee,
+    #[msg("Treasury token account does not match the protocol")]
+    InvalidTreasuryAccount,
+    #[msg("Policy has not reached its end time")]
+    PolicyNotExpired,
+    #[msg("Policy has a pending claim")]
+    ClaimPending,
+    #[msg("Policy is still active")]
+    PolicyStillActive,
+    #[msg("Token mint is not insurable under this product")]
+    MintNotInsurable,
+    #[msg("Token mint does not match the insured mint")]
+    InsuredMintMismatch,
+    #[msg("Product registry is full")]
+    ProductRegistryFull,
+    #[msg("Invalid duration")]
+    InvalidDuration,
+    #[msg("Price oracle is inactive")]
+    OracleInactive,
+    #[msg("Invalid oracle parameters")]
+    InvalidOracleParams,
+    #[msg("Invalid assessor committee")]
+    InvalidCommittee,
+    #[msg("Signer is not a committee member")]
+    NotCommitteeMember,
+    #[msg("Claims must be decided by the assessor committee")]
+    CommitteeReviewRequired,
+    #[msg("Optimistic claims are not enabled")]
+    OptimisticClaimsDisabled,
+    #[msg("Invalid optimistic claim parameters")]
+    InvalidOptimisticParams,
+    #[msg("Bond token accounts are required")]
+    BondAccountRequired,
+    #[msg("Bond vault does not match the protocol")]
+    InvalidBondVault,
+    #[msg("Claim is not an optimistic claim")]
+    NotOptimisticClaim,
+    #[msg("Challenge period has ended")]
+    ChallengePeriodEnded,
+    #[msg("Challenge period is still active")]
+    ChallengePeriodActive,
+    #[msg("Claim is not rejected")]
+    ClaimNotRejected,
+    #[msg("Claim is not under appeal")]
+    ClaimNotAppealed,
+    #[msg("Appeal window has closed")]
+    AppealWindowClosed,
+    #[msg("Maximum number of appeals reached")]
+    AppealLimitReached,
+    #[msg("Appeal reviewer must differ from the protocol authority and claims assessor")]
+    InvalidAppealReviewer,
+    #[msg("Invalid deductible")]
+    InvalidDeductible,
+    #[msg("Invalid waiting period")]
+    InvalidWaitingPeriod,
+    #[msg("Invalid grace period")]
+    InvalidGracePeriod,
+    #[msg("Claims are not accepted during the waiting period")]
+    ClaimInWaitingPeriod,
+    #[msg("Claim grace period has ended")]
+    ClaimGracePeriodEnded,
+    #[msg("Claim amount does not exceed the deductible")]
+    ClaimBelowDeductible,
+    #[msg("Invalid no-claims discount")]
+    InvalidNoClaimsDiscount,
+    #[msg("Signer does not hold the policy token")]
+    NotPolicyHolder,
+    #[msg("Claims assessor must differ from the appeal reviewer")]
+    InvalidClaimsAssessor,
+    #[msg("Execution time is earlier than the timelock delay allows")]
+    TimelockTooShort,
+    #[msg("Timelock has not expired")]
+    TimelockNotExpired,
+    #[msg("Invalid timelock delay")]
+    InvalidTimelockDelay,
+    #[msg("Account does not match the queued admin action")]
+    AdminActionAccountMismatch,
+    #[msg("Operation is paused for this product")]
+    ProductPaused,
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags,
+    #[msg("Invalid cancellation terms")]
+    InvalidCancellationTerms,
+    #[msg("Policy is within its minimum holding period")]
+    MinHoldingPeriodActive,
+    #[msg("Policy is not within the renewal window")]
+    RenewalWindowNotOpen,
+    #[msg("Policy already has a pending renewal")]
+    RenewalPending,
+    #[msg("Payout exceeds the loss implied by the oracle price drop")]
+    PayoutExceedsPriceDrop,
+    #[msg("Reserve drop has not persisted for the confirmation period")]
+    RugPullNotConfirmed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 价格 1.0 (`PRICE_DECIMALS` 位小数)
+    const ONE: u128 = 1_000_000_000_000_000_000;
+
+    fn pyth_price_data(expo: i32, ema_price: i64, ema_conf: u64, publish_time: i64, status: u32) -> Vec<u8> {
+        let mut data = vec![0u8; PYTH_PRICE_ACCOUNT_MIN_LEN];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[4..8].copy_from_slice(&PYTH_VERSION_2.to_le_bytes());
+        data[8..12].copy_from_slice(&PYTH_ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[20..24].copy_from_slice(&expo.to_le_bytes());
+        data[48..56].copy_from_slice(&ema_price.to_le_bytes());
+        data[72..80].copy_from_slice(&ema_conf.to_le_bytes());
+        data[96..104].copy_from_slice(&publish_time.to_le_bytes());
+        data[224..228].copy_from_slice(&status.to_le_bytes());
+        data
+    }
+
+    fn oracle_config(oracle_account: Pubkey) -> OracleConfig {
+        OracleConfig {
+            token_mint: Pubkey::new_unique(),
+            oracle_account,
+            oracle_kind: OracleKind::PythV2,
+            max_staleness: 600,
+            max_confidence: 100,
+            is_active: true,
+            bump: 255,
+        }
+    }
+
+    fn terms() -> ProductTerms {
+        ProductTerms {
+            premium_rate: 500,
+            coverage_rate: 8000,
+            min_coverage: 1_000,
+            max_coverage: 1_000_000_000,
+            duration_days: 30,
+            trigger_threshold: 5000,
+            deductible: Deductible::None,
+            waiting_period: 0,
+            grace_period: 0,
+            no_claims_discount: 0,
+            cancellation_fee: 0,
+            min_holding_period: 0,
+            non_refundable_rate: 0,
+        }
+    }
+
+    fn bonding_curve_data(virtual_sol_reserves: u64, real_sol_reserves: u64, complete: bool) -> Vec<u8> {
+        let mut data = vec![0u8; BONDING_CURVE_MIN_LEN];
+        data[..8].copy_from_slice(&BONDING_CURVE_DISCRIMINATOR);
+        data[16..24].copy_from_slice(&virtual_sol_reserves.to_le_bytes());
+        data[32..40].copy_from_slice(&real_sol_reserves.to_le_bytes());
+        data[48] = complete as u8;
+        data
+    }
+
+    fn load_curve(owner: Pubkey, mut data: Vec<u8>) -> Result<BondingCurveState> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        BondingCurveState::load(&account)
+    }
+
+    /// 保额 1_000_000、保费 50_000、期限 [0, 30 天] 的有效保单
+    fn policy() -> InsurancePolicy {
+        InsurancePolicy {
+            owner: Pubkey::new_unique(),
+            policy_mint: Pubkey::new_unique(),
+            product: Pubkey::new_unique(),
+            index: 0,
+            insured_mint: Pubkey::new_unique(),
+            terms: terms(),
+            coverage_amount: 1_000_000,
+            remaining_coverage: 1_000_000,
+            claim_count: 0,
+            premium_paid: 50_000,
+            start_time: 0,
+            cover_start: 0,
+            end_time: 30 * 86400,
+            renewal_start: 0,
+            renewal_premium: 0,
+            renewal_terms: terms(),
+            renewal_entry_price: 0,
+            renewal_price_oracle: Pubkey::default(),
+            renewal_bonding_curve: Pubkey::default(),
+            renewal_real_sol_reserves: 0,
+            status: PolicyStatus::Active,
+            has_pending_claim: false,
+            entry_price: 2 * ONE,
+            price_oracle: Pubkey::new_unique(),
+            bonding_curve: Pubkey::default(),
+            snapshot_real_sol_reserves: 0,
+            rug_pull_observed_slot: 0,
+            bump: 255,
+        }
+    }
+
+    /// 以给定 owner 构造价格账户并读取价格，窗口为 [1_000, 2_000]
+    fn read_pyth(owner: Pubkey, mut data: Vec<u8>) -> Result<u128> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        get_pyth_ema_price(&account, &oracle_config(key), 1_000, 2_000)
+    }
+
+    #[test]
+    fn pyth_parse_reads_price_fields() {
+        let price = PythPrice::parse(&pyth_price_data(-8, 150_000_000, 10_000, 1_500, PYTH_STATUS_TRADING)).unwrap();
+        assert_eq!(price.expo, -8);
+        assert_eq!(price.ema_price, 150_000_000);
+        assert_eq!(price.ema_conf, 10_000);
+        assert_eq!(price.publish_time, 1_500);
+        assert_eq!(price.status, PYTH_STATUS_TRADING);
+    }
+
+    #[test]
+    fn pyth_parse_rejects_wrong_header() {
+        for offset in [0, 4, 8] {
+            let mut data = pyth_price_data(-8, 1, 0, 0, PYTH_STATUS_TRADING);
+            data[offset] ^= 0xff;
+            assert_eq!(
+                PythPrice::parse(&data).err().unwrap(),
+                ErrorCode::InvalidOracleAccount.into()
+            );
+        }
+    }
+
+    #[test]
+    fn pyth_parse_rejects_short_buffer() {
+        let data = pyth_price_data(-8, 1, 0, 0, PYTH_STATUS_TRADING);
+        for len in [0, 12, PYTH_PRICE_ACCOUNT_MIN_LEN - 1] {
+            assert_eq!(
+                PythPrice::parse(&data[..len]).err().unwrap(),
+                ErrorCode::InvalidOracleAccount.into()
+            );
+        }
+    }
+
+    #[test]
+    fn pyth_price_is_scaled_to_price_decimals() {
+        let data = pyth_price_data(-8, 150_000_000, 10_000, 1_500, PYTH_STATUS_TRADING);
+        assert_eq!(read_pyth(PYTH_PROGRAM_ID, data).unwrap(), 15 * ONE / 10);
+    }
+
+    #[test]
+    fn pyth_price_rejects_wrong_owner() {
+        let data = pyth_price_data(-8, 150_000_000, 10_000, 1_500, PYTH_STATUS_TRADING);
+        assert_eq!(
+            read_pyth(Pubkey::new_unique(), data).unwrap_err(),
+            ErrorCode::InvalidOracleOwner.into()
+        );
+    }
+
+    #[test]
+    fn pyth_price_rejects_stale_price() {
+        // 在窗口内发布，但距窗口结束超过 max_staleness
+        let data = pyth_price_data(-8, 150_000_000, 10_000, 1_300, PYTH_STATUS_TRADING);
+        assert_eq!(read_pyth(PYTH_PROGRAM_ID, data).unwrap_err(), ErrorCode::OraclePriceStale.into());
+    }
+
+    #[test]
+    fn pyth_price_rejects_wide_confidence() {
+        // 置信区间 2% > 上限 1%
+        let data = pyth_price_data(-8, 150_000_000, 3_000_000, 1_500, PYTH_STATUS_TRADING);
+        assert_eq!(
+            read_pyth(PYTH_PROGRAM_ID, data).unwrap_err(),
+            ErrorCode::OracleConfidenceTooWide.into()
+        );
+    }
+
+    #[test]
+    fn pyth_price_rejects_non_trading_status() {
+        let data = pyth_price_data(-8, 150_000_000, 10_000, 1_500, 0);
+        assert_eq!(
+            read_pyth(PYTH_PROGRAM_ID, data).unwrap_err(),
+            ErrorCode::OraclePriceUnavailable.into()
+        );
+    }
+
+    #[test]
+    fn pyth_price_rejects_publish_time_outside_window() {
+        for publish_time in [999, 2_001] {
+            let data = pyth_price_data(-8, 150_000_000, 10_000, publish_time, PYTH_STATUS_TRADING);
+            assert_eq!(
+                read_pyth(PYTH_PROGRAM_ID, data).unwrap_err(),
+                ErrorCode::OraclePriceOutsideWindow.into()
+            );
+        }
+    }
+
+    #[test]
+    fn scale_price_handles_negative_exponents() {
+        assert_eq!(scale_price(150_000_000, -8).unwrap(), 15 * ONE / 10);
+        assert_eq!(scale_price(1_500_000, -6).unwrap(), 15 * ONE / 10);
+        assert_eq!(scale_price(15_000, -4).unwrap(), 15 * ONE / 10);
+    }
+
+    #[test]
+    fn scale_price_keeps_low_prices() {
+        // pump.fun 代币价格通常在 1e-5 到 1e-8 美元之间
+        assert_eq!(scale_price(99, -8).unwrap(), 990_000_000_000);
+        assert_eq!(scale_price(1_234, -12).unwrap(), 1_234_000_000);
+        assert_eq!(scale_price(1, -18).unwrap(), 1);
+    }
+
+    #[test]
+    fn scale_price_handles_positive_exponents() {
+        assert_eq!(scale_price(15, 2).unwrap(), 1_500 * ONE);
+        assert_eq!(scale_price(7, 0).unwrap(), 7 * ONE);
+    }
+
+    #[test]
+    fn scale_price_rejects_zero() {
+        assert_eq!(scale_price(0, -8).unwrap_err(), ErrorCode::InvalidOraclePrice.into());
+        assert_eq!(scale_price(9, -19).unwrap_err(), ErrorCode::InvalidOraclePrice.into());
+        assert_eq!(scale_price(1, -26).unwrap_err(), ErrorCode::InvalidOraclePrice.into());
+    }
+
+    #[test]
+    fn scale_price_rejects_overflow() {
+        assert_eq!(scale_price(u64::MAX, 0).unwrap_err(), ErrorCode::InvalidOraclePrice.into());
+        assert_eq!(scale_price(1, 17).unwrap_err(), ErrorCode::InvalidOraclePrice.into());
+        assert_eq!(scale_price(1, 30).unwrap_err(), ErrorCode::InvalidOraclePrice.into());
+    }
+
+    #[test]
+    fn price_drop_payout_is_bounded_by_oracle_drop() {
+        let policy = policy();
+        // 入场价 2.0 跌至 1.5，跌幅 25%
+        assert!(verify_price_drop_payout(&policy, 15 * ONE / 10, 250_000).is_ok());
+        assert_eq!(
+            verify_price_drop_payout(&policy, 15 * ONE / 10, 250_001).unwrap_err(),
+            ErrorCode::PayoutExceedsPriceDrop.into()
+        );
+        assert_eq!(
+            verify_price_drop_payout(&policy, 25 * ONE / 10, 1).unwrap_err(),
+            ErrorCode::PayoutExceedsPriceDrop.into()
+        );
+    }
+
+    #[test]
+    fn price_drop_payout_is_unbounded_without_entry_price() {
+        let policy = InsurancePolicy { entry_price: 0, ..policy() };
+        assert!(verify_price_drop_payout(&policy, 25 * ONE / 10, 1_000_000).is_ok());
+    }
+
+    #[test]
+    fn drop_bps_measures_relative_drop() {
+        assert_eq!(drop_bps(2_000_000, 1_500_000), 2500);
+        assert_eq!(drop_bps(2_000_000, 0), 10000);
+        assert_eq!(drop_bps(3, 2), 3333);
+    }
+
+    #[test]
+    fn drop_bps_ignores_rises_and_missing_baseline() {
+        assert_eq!(drop_bps(2_000_000, 2_000_000), 0);
+        assert_eq!(drop_bps(2_000_000, 3_000_000), 0);
+        assert_eq!(drop_bps(0, 1_000), 0);
+        assert_eq!(drop_bps(MAX_SCALED_PRICE, 0), 10000);
+    }
+
+    #[test]
+    fn bonding_curve_load_reads_reserves() {
+        let curve = load_curve(PUMP_FUN_PROGRAM_ID, bonding_curve_data(30_000_000_000, 2_000_000_000, false)).unwrap();
+        assert_eq!(curve.real_sol_reserves, 2_000_000_000);
+        assert!(!curve.complete);
+
+        let curve = load_curve(PUMP_FUN_PROGRAM_ID, bonding_curve_data(0, 0, true)).unwrap();
+        assert!(curve.complete);
+    }
+
+    #[test]
+    fn bonding_curve_load_rejects_wrong_owner() {
+        assert_eq!(
+            load_curve(Pubkey::new_unique(), bonding_curve_data(1, 1, false)).err().unwrap(),
+            ErrorCode::InvalidBondingCurveOwner.into()
+        );
+    }
+
+    #[test]
+    fn bonding_curve_load_rejects_invalid_data() {
+        let mut data = bonding_curve_data(1, 1, false);
+        data[0] ^= 0xff;
+        assert_eq!(
+            load_curve(PUMP_FUN_PROGRAM_ID, data).err().unwrap(),
+            ErrorCode::InvalidBondingCurveAccount.into()
+        );
+
+        let mut data = bonding_curve_data(1, 1, false);
+        data.truncate(BONDING_CURVE_MIN_LEN - 1);
+        assert_eq!(
+            load_curve(PUMP_FUN_PROGRAM_ID, data).err().unwrap(),
+            ErrorCode::InvalidBondingCurveAccount.into()
+        );
+    }
+
+    #[test]
+    fn oracle_is_required_for_priced_price_drop_policies() {
+        let policy = policy();
+        assert_eq!(
+            verify_oracle_payout(&policy, InsuranceType::PriceDrop, None, None, 1, 0).unwrap_err(),
+            ErrorCode::OracleRequired.into()
+        );
+        assert!(verify_oracle_payout(&policy, InsuranceType::RugPull, None, None, 1, 0).is_ok());
+
+        let unpriced = InsurancePolicy { entry_price: 0, ..policy };
+        assert!(verify_oracle_payout(&unpriced, InsuranceType::PriceDrop, None, None, 1, 0).is_ok());
+    }
+
+    #[test]
+    fn oracle_must_match_the_feed_recorded_on_the_policy() {
+        let policy = policy();
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = pyth_price_data(-8, 150_000_000, 10_000, 1_500, PYTH_STATUS_TRADING);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &PYTH_PROGRAM_ID, false, 0);
+        assert_eq!(
+            verify_oracle_payout(
+                &policy,
+                InsuranceType::PriceDrop,
+                Some(&account),
+                Some(&oracle_config(key)),
+                1,
+                2_000,
+            )
+            .unwrap_err(),
+            ErrorCode::OracleMismatch.into()
+        );
+
+        let policy = InsurancePolicy { price_oracle: key, ..policy };
+        assert!(verify_oracle_payout(
+            &policy,
+            InsuranceType::PriceDrop,
+            Some(&account),
+            Some(&oracle_config(key)),
+            250_000,
+            2_000,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn renewed_term_starts_at_the_previous_end_time() {
+        let mut policy = InsurancePolicy {
+            remaining_coverage: 400_000,
+            renewal_start: 30 * 86400,
+            renewal_premium: 40_000,
+            end_time: 60 * 86400,
+            ..policy()
+        };
+
+        assert_eq!(policy.start_renewed_term(30 * 86400 - 1), 0);
+        assert_eq!(policy.remaining_coverage, 400_000);
+
+        assert_eq!(policy.start_renewed_term(30 * 86400), 600_000);
+        assert_eq!(policy.remaining_coverage, 1_000_000);
+        assert_eq!(policy.start_time, 30 * 86400);
+        assert_eq!(policy.premium_paid, 40_000);
+        assert_eq!(policy.renewal_start, 0);
+        assert_eq!(policy.start_renewed_term(45 * 86400), 0);
+    }
+
+    #[test]
+    fn renewed_term_waits_for_pending_claims_and_revives_claimed_policies() {
+        let mut policy = InsurancePolicy {
+            remaining_coverage: 0,
+            status: PolicyStatus::Claimed,
+            has_pending_claim: true,
+            renewal_start: 30 * 86400,
+            renewal_premium: 40_000,
+            ..policy()
+        };
+        assert_eq!(policy.start_renewed_term(31 * 86400), 0);
+
+        policy.has_pending_claim = false;
+        assert_eq!(policy.start_renewed_term(31 * 86400), 1_000_000);
+        assert_eq!(policy.status, PolicyStatus::Active);
+    }
+
+    #[test]
+    fn pending_renewal_premium_is_fully_refundable() {
+        let policy = InsurancePolicy {
+            renewal_start: 30 * 86400,
+            renewal_premium: 40_000,
+            end_time: 60 * 86400,
+            ..policy()
+        };
+        // 当期过半，当期保费退一半，新一期保费全退
+        let quote = calculate_refund(&policy, 0, 15 * 86400);
+        assert_eq!(quote.unearned_premium, 25_000 + 40_000);
+        assert_eq!(quote.refund, 65_000);
+    }
+
+    #[test]
+    fn deductible_apply() {
+        assert_eq!(Deductible::None.apply(1_000), 1_000);
+        assert_eq!(Deductible::Flat(300).apply(1_000), 700);
+        assert_eq!(Deductible::Flat(1_500).apply(1_000), 0);
+        assert_eq!(Deductible::Bps(2500).apply(1_000), 750);
+        assert_eq!(Deductible::Bps(10000).apply(1_000), 0);
+        assert_eq!(Deductible::Bps(0).apply(u64::MAX / 10000), u64::MAX / 10000);
+    }
+
+    #[test]
+    fn premium_splits_treasury_fee() {
+        assert_eq!(calculate_premium(1_000_000, 500, 0, 1000), (50_000, 5_000, 45_000));
+        assert_eq!(calculate_premium(1_000_000, 500, 2000, 1000), (40_000, 4_000, 36_000));
+        assert_eq!(calculate_premium(1_000_000, 500, 0, 0), (50_000, 0, 50_000));
+        assert_eq!(calculate_premium(999, 1, 0, 1000), (0, 0, 0));
+    }
+
+    #[test]
+    fn refund_is_pro_rata_without_fees() {
+        let policy = policy();
+        assert_eq!(calculate_refund(&policy, 1000, 0).refund, 50_000);
+        assert_eq!(calculate_refund(&policy, 1000, 15 * 86400).refund, 25_000);
+        assert_eq!(calculate_refund(&policy, 1000, 30 * 86400).refund, 0);
+    }
+
+    #[test]
+    fn refund_applies_floor_and_cancellation_fee() {
+        let policy = InsurancePolicy {
+            terms: ProductTerms {
+                cancellation_fee: 1000,
+                non_refundable_rate: 5000,
+                ..terms()
+            },
+            ..policy()
+        };
+        // 10 天后未赚保费 33_330，受 50% 不可退比例限制为 25_000
+        let quote = calculate_refund(&policy, 2000, 10 * 86400);
+        assert_eq!(quote.unearned_premium, 33_330);
+        assert_eq!(quote.refundable_premium, 25_000);
+        assert_eq!(quote.cancellation_fee, 2_500);
+        assert_eq!(quote.refund, 22_500);
+        assert_eq!(quote.treasury_fee, 500);
+        assert_eq!(quote.net_to_pool, 2_000);
+    }
+
+    #[test]
+    fn drop_bps_compares_low_prices_at_full_precision() {
+        // 入场 1.4e-6、当前 0.9e-6 的真实跌幅约 35.7%，不应按全额赔付
+        let entry = scale_price(140, -8).unwrap();
+        let current = scale_price(90, -8).unwrap();
+        assert_eq!(drop_bps(entry, current), 3571);
+
+        // 真实跌幅 40% 不得通过 50% 的阈值
+        let entry = scale_price(1_234_567, -12).unwrap();
+        let current = scale_price(740_740, -12).unwrap();
+        assert!(drop_bps(entry, current) < 5000);
+    }
+
+    #[test]
+    fn price_drop_payout_is_bounded_at_full_precision() {
+        let policy = InsurancePolicy { entry_price: scale_price(140, -8).unwrap(), ..policy() };
+        let current = scale_price(90, -8).unwrap();
+        assert!(verify_price_drop_payout(&policy, current, 357_100).is_ok());
+        assert_eq!(
+            verify_price_drop_payout(&policy, current, 357_101).unwrap_err(),
+            ErrorCode::PayoutExceedsPriceDrop.into()
+        );
+    }
+
+    #[test]
+    fn recorded_feed_still_verifies_after_rotation_or_deactivation() {
+        let key = Pubkey::new_unique();
+        let policy = InsurancePolicy { price_oracle: key, ..policy() };
+        let mut lamports = 0;
+        let mut data = pyth_price_data(-8, 150_000_000, 10_000, 1_500, PYTH_STATUS_TRADING);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &PYTH_PROGRAM_ID, false, 0);
+
+        let rotated = oracle_config(Pubkey::new_unique());
+        let deactivated = OracleConfig { is_active: false, ..oracle_config(key) };
+        for config in [rotated, deactivated] {
+            assert!(verify_oracle_payout(
+                &policy,
+                InsuranceType::PriceDrop,
+                Some(&account),
+                Some(&config),
+                250_000,
+                2_000,
+            )
+            .is_ok());
+            assert_eq!(
+                verify_oracle_payout(
+                    &policy,
+                    InsuranceType::PriceDrop,
+                    Some(&account),
+                    Some(&config),
+                    250_001,
+                    2_000,
+                )
+                .unwrap_err(),
+                ErrorCode::PayoutExceedsPriceDrop.into()
+            );
+        }
+    }
+
+    #[test]
+    fn rug_pull_drop_is_measured_on_real_reserves() {
+        // 投保时虚拟 35 SOL / 真实 5 SOL，撤池后虚拟 31 SOL / 真实 1 SOL：虚拟储备仅跌约 11%
+        let curve = load_curve(PUMP_FUN_PROGRAM_ID, bonding_curve_data(31_000_000_000, 1_000_000_000, false)).unwrap();
+        assert_eq!(curve.real_reserve_drop_bps(5_000_000_000), 8000);
+        assert_eq!(curve.real_reserve_drop_bps(1_000_000_000), 0);
+        assert_eq!(curve.real_reserve_drop_bps(0), 0);
+    }
+
+    #[test]
+    fn renewed_term_keeps_the_current_baseline_until_it_starts() {
+        let oracle = Pubkey::new_unique();
+        let mut policy = InsurancePolicy {
+            renewal_start: 30 * 86400,
+            renewal_premium: 40_000,
+            renewal_terms: ProductTerms { trigger_threshold: 3000, ..terms() },
+            renewal_entry_price: ONE,
+            renewal_price_oracle: oracle,
+            rug_pull_observed_slot: 7,
+            end_time: 60 * 86400,
+            ..policy()
+        };
+
+        policy.start_renewed_term(30 * 86400 - 1);
+        assert_eq!(policy.entry_price, 2 * ONE);
+        assert_eq!(policy.terms.trigger_threshold, terms().trigger_threshold);
+        assert_eq!(policy.rug_pull_observed_slot, 7);
+
+        policy.start_renewed_term(30 * 86400);
+        assert_eq!(policy.entry_price, ONE);
+        assert_eq!(policy.price_oracle, oracle);
+        assert_eq!(policy.terms.trigger_threshold, 3000);
+        assert_eq!(policy.rug_pull_observed_slot, 0);
+        assert_eq!(policy.renewal_entry_price, 0);
+    }
+
+    #[test]
+    fn renewed_term_does_not_restart_the_waiting_period() {
+        let mut policy = InsurancePolicy {
+            renewal_start: 30 * 86400,
+            renewal_premium: 40_000,
+            end_time: 60 * 86400,
+            ..policy()
+        };
+        policy.start_renewed_term(30 * 86400);
+        assert_eq!(policy.start_time, 30 * 86400);
+        assert_eq!(policy.cover_start, 0);
+    }
+
+    #[test]
+    fn first_deposit_locks_minimum_shares() {
+        assert_eq!(deposit_shares(1_000_000, 0, 0).unwrap(), (999_000, 1_000));
+        // 注资前已有的保费按 1:1 锁定
+        assert_eq!(deposit_shares(1_000_000, 50_000, 0).unwrap(), (999_000, 51_000));
+        assert_eq!(
+            deposit_shares(MIN_LOCKED_SHARES, 0, 0).unwrap_err(),
+            ErrorCode::InvalidAmount.into()
+        );
+    }
+
+    #[test]
+    fn deposits_are_priced_at_pool_value() {
+        assert_eq!(deposit_shares(500, 2_000, 1_000).unwrap(), (250, 0));
+        assert_eq!(deposit_shares(500, 1_000, 2_000).unwrap(), (1_000, 0));
+        assert_eq!(deposit_shares(1, 3_000, 1_000).unwrap_err(), ErrorCode::InvalidAmount.into());
+        assert_eq!(deposit_shares(1, 0, 1_000).unwrap_err(), ErrorCode::PoolInsolvent.into());
+        assert_eq!(deposit_shares(u64::MAX, 1, 2).unwrap_err(), ErrorCode::InvalidAmount.into());
+    }
+
+    #[test]
+    fn locked_shares_make_donation_attacks_unprofitable() {
+        // 攻击者首次注资后向资金池直接转入 1_000_000，稀释后续注资的份额
+        let (attacker_shares, locked_shares) = deposit_shares(MIN_LOCKED_SHARES + 1, 0, 0).unwrap();
+        let supply = attacker_shares + locked_shares;
+        let pool = MIN_LOCKED_SHARES + 1 + 1_000_000;
+
+        let (victim_shares, _) = deposit_shares(1_000_000, pool, supply).unwrap();
+        let (supply, pool) = (supply + victim_shares, pool + 1_000_000);
+        let attacker_out = withdrawal_amount(attacker_shares, pool, supply);
+        let victim_out = withdrawal_amount(victim_shares, pool, supply);
+
+        assert!(attacker_out < MIN_LOCKED_SHARES + 1 + 1_000_000);
+        assert!(victim_out >= 1_000_000 - 1_000);
+    }
+
+    #[test]
+    fn withdrawals_are_priced_at_pool_value() {
+        assert_eq!(withdrawal_amount(250, 2_000, 1_000), 500);
+        assert_eq!(withdrawal_amount(1_000, 0, 1_000), 0);
+    }
+
+    #[test]
+    fn drained_pool_blocks_deposits_until_recapitalized() {
+        assert!(!pool_drained(0, 0));
+        assert!(!pool_drained(1, 1_000));
+        assert!(pool_drained(0, 1_000));
+        assert!(pool_drained(999, 1_000_000));
+        assert_eq!(deposit_shares(1_000_000, 999, 1_000_000).unwrap_err(), ErrorCode::PoolInsolvent.into());
+
+        // 重新募资后新一代份额从零开始，残余资金按 1:1 锁定
+        assert_eq!(deposit_shares(1_000_000, 999, 0).unwrap(), (999_000, 1_999));
+    }
+
+    /// 默认杠杆、无待赎回份额的协议
+    fn protocol() -> InsuranceProtocol {
+        InsuranceProtocol {
+            authority: Pubkey::new_unique(),
+            pending_authority: Pubkey::default(),
+            guardian: Pubkey::default(),
+            claims_assessor: Pubkey::default(),
+            assessor_committee: Pubkey::default(),
+            treasury: Pubkey::new_unique(),
+            treasury_fee: 0,
+            total_policies: 0,
+            product_count: 0,
+            total_claims: 0,
+            total_payouts: 0,
+            total_premium_fees: 0,
+            total_cancellation_fees: 0,
+            pause_flags: 0,
+            capital_mint: Pubkey::new_unique(),
+            insurance_pool: Pubkey::new_unique(),
+            share_mint: Pubkey::new_unique(),
+            share_escrow: Pubkey::new_unique(),
+            share_epoch: 0,
+            withdrawal_cooldown: 0,
+            pending_withdrawal_shares: 0,
+            active_coverage: 0,
+            max_leverage: DEFAULT_MAX_LEVERAGE,
+            bond_vault: Pubkey::default(),
+            challenge_period: 0,
+            claim_bond_rate: 0,
+            min_claim_bond: 0,
+            appeal_reviewer: Pubkey::default(),
+            product_timelock: DEFAULT_TIMELOCK_DELAY,
+            oracle_timelock: DEFAULT_TIMELOCK_DELAY,
+            fee_timelock: DEFAULT_TIMELOCK_DELAY,
+            queued_action_count: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn capacity_scales_pool_balance_by_leverage() {
+        let protocol = protocol();
+        assert_eq!(underwriting_capacity(&protocol, 1_000_000, 1_000_000), 1_000_000);
+        // 尚无份额时 (如首次注资前收取的保费) 按资金池余额计算
+        assert_eq!(underwriting_capacity(&protocol, 50_000, 0), 50_000);
+
+        let levered = InsuranceProtocol { max_leverage: 25000, ..protocol };
+        assert_eq!(underwriting_capacity(&levered, 1_000_000, 1_000_000), 2_500_000);
+    }
+
+    #[test]
+    fn capacity_excludes_pending_withdrawal_shares() {
+        let protocol = InsuranceProtocol { pending_withdrawal_shares: 250_000, ..protocol() };
+        assert_eq!(underwriting_capacity(&protocol, 2_000_000, 1_000_000), 1_500_000);
+
+        let all_pending = InsuranceProtocol { pending_withdrawal_shares: 1_000_000, ..protocol };
+        assert_eq!(underwriting_capacity(&all_pending, 2_000_000, 1_000_000), 0);
+    }
+
+    #[test]
+    fn claim_bond_has_a_floor() {
+        assert_eq!(claim_bond(1_000_000, 500, 1_000), 50_000);
+        assert_eq!(claim_bond(19, 500, 1_000), 1_000);
+        assert_eq!(claim_bond(0, 500, 1_000), 1_000);
+        assert_eq!(
+            validate_optimistic_params(86400, 500, 0).unwrap_err(),
+            ErrorCode::InvalidOptimisticParams.into()
+        );
+    }
 }